@@ -1,11 +1,50 @@
-use super::CommonOpt;
-use crate::cli::{get_swap_calldata, utils::check_required_files};
-use alloy::primitives::utils::parse_ether;
-use anyhow::Result;
+use super::{BlockOpt, CommonOpt, HookOpt, PowOpt};
+use crate::cli::utils::{
+    BurnStatus, burn_proof_path, check_burn_commitment, check_required_files, entry_burn_address,
+    entry_burn_key, entry_byte_security_relax, entry_commitment, entry_extra_commitment,
+    entry_intended_amount, entry_mint_after, entry_prover_fee, entry_str, find_entry, read_entries,
+    update_entry,
+};
+use crate::fp::Fp;
+use crate::server::types::ProofOutput;
+use crate::utils::{
+    BETH, BurnCommitment, RapidsnarkOutput, compute_nullifier, compute_remaining_coin,
+};
+use alloy::primitives::utils::{format_ether, parse_ether};
+use alloy::primitives::{Address, B256, Bytes, U256};
+use alloy::providers::Provider;
+use anyhow::{Context, Result, bail};
+use qrcode::QrCode;
+use qrcode::render::unicode::Dense1x2;
+use serde_json::{Value, json};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use structopt::StructOpt;
 
+/// Upper bound on the number of burns a single `--split` may create
+const MAX_SPLIT_BURNS: usize = 100;
+
+#[derive(StructOpt)]
+pub enum BurnOpt {
+    /// Burn ETH, then prove and mint it right away (send + prove + mint)
+    Run(BurnRunOpt),
+    /// Send ETH to a fresh burn address and store the burn record
+    Send(BurnSendOpt),
+    /// Store a burn record and print its burn address, to be funded from another wallet
+    Prepare(BurnSendOpt),
+    /// Generate the proof-of-burn of a sent burn
+    Prove(BurnProveOpt),
+    /// Store a proof-of-burn generated by an external prover
+    ImportProof(BurnImportProofOpt),
+    /// Prove a burn if needed and broadcast its mintCoin
+    Mint(BurnMintOpt),
+    /// Continue unfinished burns from wherever they stopped
+    Resume(BurnResumeOpt),
+}
+
 #[derive(StructOpt)]
-pub struct BurnOpt {
+pub struct BurnSendOpt {
     #[structopt(flatten)]
     common_opt: CommonOpt,
     #[structopt(long)]
@@ -14,70 +53,1007 @@ pub struct BurnOpt {
     fee: String,
     #[structopt(long, default_value = "0")]
     spend: String,
+    /// Spread an amount above BETH.MINT_CAP over several burns of random sizes
+    #[structopt(long)]
+    split: bool,
+    /// Largest amount of a single burn of a split, in ETH (default: BETH.MINT_CAP)
+    #[structopt(long)]
+    max_chunk: Option<String>,
+    /// Address receiving the minted BETH (default: the burning wallet)
+    #[structopt(long)]
+    receiver: Option<Address>,
+    /// External prover that will generate the proof-of-burn (default: whichever
+    /// wallet runs `burn prove`)
+    #[structopt(long)]
+    prover: Option<Address>,
+    /// Fee paid to the prover out of the burned amount, in ETH
+    #[structopt(long, default_value = "0")]
+    prover_fee: String,
+    #[structopt(flatten)]
+    hook_opt: HookOpt,
+    #[structopt(flatten)]
+    schedule_opt: MintScheduleOpt,
+    #[structopt(flatten)]
+    pow_opt: PowOpt,
+}
+
+/// Minting right after burning links the two transactions by their timing, so
+/// the proof and mint of a burn can be postponed. The schedule is stored with
+/// the burn and honored by `burn mint`, `burn resume` and `watch-burns`.
+#[derive(StructOpt)]
+pub struct MintScheduleOpt {
+    /// Wait before proving and minting: a delay such as 2h, or a range such
+    /// as 1h..6h to wait a random time within
+    #[structopt(long, conflicts_with = "mint_at")]
+    mint_delay: Option<MintDelay>,
+    /// Don't prove and mint before this time, e.g. "2026-01-31 18:00:00" (UTC)
+    #[structopt(long, parse(try_from_str = humantime::parse_rfc3339_weak))]
+    mint_at: Option<SystemTime>,
+}
+
+impl MintScheduleOpt {
+    /// Earliest mint time of a burn sent now. Every call draws a new delay.
+    fn mint_after(&self) -> Option<SystemTime> {
+        match (&self.mint_delay, self.mint_at) {
+            (Some(delay), _) => Some(SystemTime::now() + delay.sample()),
+            (None, mint_at) => mint_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MintDelay {
+    min: Duration,
+    max: Duration,
+}
+
+impl MintDelay {
+    fn sample(&self) -> Duration {
+        let span = (self.max - self.min).as_secs();
+        let random = u64::from_be_bytes(B256::random().0[..8].try_into().unwrap());
+        self.min + Duration::from_secs(random % (span + 1))
+    }
+}
+
+impl FromStr for MintDelay {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (min, max) = s.split_once("..").unwrap_or((s, s));
+        let min = humantime::parse_duration(min.trim())?;
+        let max = humantime::parse_duration(max.trim())?;
+        if min > max {
+            bail!("the mint delay range {} is empty", s);
+        }
+        Ok(MintDelay { min, max })
+    }
+}
+
+#[derive(StructOpt)]
+pub struct BurnRunOpt {
+    #[structopt(flatten)]
+    send_opt: BurnSendOpt,
+    #[structopt(flatten)]
+    block_opt: BlockOpt,
+}
+
+#[derive(StructOpt)]
+pub struct BurnProveOpt {
+    #[structopt(flatten)]
+    common_opt: CommonOpt,
+    #[structopt(flatten)]
+    block_opt: BlockOpt,
+    /// Amount the proof commits to, in ETH (default: the amount of the burn).
+    /// Anything the burn address holds beyond it stays burnt.
+    #[structopt(long)]
+    intended_amount: Option<String>,
+    /// Address the proof credits as its prover (default: the wallet). Only a
+    /// burn without prover fee can be proved for another prover than the
+    /// one it was sent for
+    #[structopt(long)]
+    prover: Option<Address>,
+    /// Id of the entry in burn.json
+    id: String,
+}
+
+#[derive(StructOpt)]
+pub struct BurnImportProofOpt {
+    /// Id of the entry in burn.json
+    id: String,
+    /// Proof returned by the prover's `/proof` endpoint
+    #[structopt(parse(from_os_str))]
+    path: std::path::PathBuf,
+}
+
+#[derive(StructOpt)]
+pub struct BurnMintOpt {
+    #[structopt(flatten)]
+    common_opt: CommonOpt,
+    #[structopt(flatten)]
+    block_opt: BlockOpt,
+    /// Id of the entry in burn.json
+    id: String,
+}
+
+#[derive(StructOpt)]
+pub struct BurnResumeOpt {
+    #[structopt(flatten)]
+    common_opt: CommonOpt,
+    #[structopt(flatten)]
+    block_opt: BlockOpt,
+    /// Only resume the burns of the split burn with this group id
+    #[structopt(long)]
+    group: Option<String>,
+    /// Only resume the burn with this id (default: every unfinished burn)
+    id: Option<String>,
 }
 
 impl BurnOpt {
-    pub async fn run(self, params_dir: &std::path::Path) -> Result<()> {
+    pub async fn run(self, params_dir: &Path) -> Result<()> {
         check_required_files(params_dir)?;
+        match self {
+            BurnOpt::Run(opt) => {
+                let common_opt = &opt.send_opt.common_opt;
+                let ids = send_burn(common_opt, params_dir, &opt.send_opt, true).await?;
+                for id in by_mint_after(params_dir, ids)? {
+                    resume_burn(common_opt, &opt.block_opt, params_dir, &id).await?;
+                }
+                Ok(())
+            }
+            BurnOpt::Send(opt) => {
+                let ids = send_burn(&opt.common_opt, params_dir, &opt, true).await?;
+                match ids.as_slice() {
+                    [id] => println!(
+                        "Burn #{} sent. Continue with: worm-miner burn prove {} && worm-miner burn mint {}",
+                        id, id, id
+                    ),
+                    _ => println!(
+                        "Burn group #{} sent ({} burns). Continue with: worm-miner burn resume --group {}",
+                        ids[0],
+                        ids.len(),
+                        ids[0]
+                    ),
+                }
+                Ok(())
+            }
+            BurnOpt::Prepare(opt) => {
+                let ids = send_burn(&opt.common_opt, params_dir, &opt, false).await?;
+                println!(
+                    "Once funded, mint with: worm-miner burn mint {}",
+                    ids.join(" && worm-miner burn mint ")
+                );
+                Ok(())
+            }
+            BurnOpt::Prove(opt) => {
+                let intended = opt
+                    .intended_amount
+                    .as_deref()
+                    .map(parse_ether)
+                    .transpose()?;
+                prove_burn(
+                    &opt.common_opt,
+                    &opt.block_opt,
+                    params_dir,
+                    &opt.id,
+                    intended,
+                    opt.prover,
+                )
+                .await
+            }
+            BurnOpt::ImportProof(opt) => import_proof(params_dir, &opt.id, &opt.path),
+            BurnOpt::Mint(opt) => {
+                resume_burn(&opt.common_opt, &opt.block_opt, params_dir, &opt.id).await
+            }
+            BurnOpt::Resume(opt) => {
+                let ids = match opt.id {
+                    Some(id) => vec![id],
+                    None => unfinished_burns(&opt.common_opt, params_dir, opt.group.as_deref())?,
+                };
+                if ids.is_empty() {
+                    println!("No unfinished burns for network {}", opt.common_opt.network);
+                }
+                for id in ids {
+                    if let Err(e) =
+                        resume_burn(&opt.common_opt, &opt.block_opt, params_dir, &id).await
+                    {
+                        println!("Burn #{} could not be resumed: {:?}", id, e);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
 
-        let amount = parse_ether(&self.amount)?;
-        let fee = parse_ether(&self.fee)?;
-        let spend = parse_ether(&self.spend)?;
+/// Ids of the burns on the selected network that are not minted yet,
+/// optionally restricted to a single burn group, the earliest due first.
+pub(crate) fn unfinished_burns(
+    common_opt: &CommonOpt,
+    params_dir: &Path,
+    group: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut ids = Vec::new();
+    for entry in read_entries(params_dir.join("burn.json"))? {
+        if entry.get("network").and_then(Value::as_str) != Some(common_opt.network.as_str()) {
+            continue;
+        }
+        if group.is_some() && entry.get("group").and_then(Value::as_str) != group {
+            continue;
+        }
+        if BurnStatus::of(&entry)? != BurnStatus::Minted {
+            ids.push(entry_str(&entry, "id")?.to_string());
+        }
+    }
+    by_mint_after(params_dir, ids)
+}
 
-        // let receiver_hook = get_swap_calldata(
-        //     parse_ether("0.0001").unwrap(),
-        //     self.common_opt.private_key.address(),
-        // );
-        let receiver_hook = Vec::new();
+/// Orders burns by their scheduled mint time, unscheduled ones first. Burns are
+/// resumed one after the other, so a later burn must not wait behind the delay
+/// of an earlier one.
+fn by_mint_after(params_dir: &Path, ids: Vec<String>) -> Result<Vec<String>> {
+    let mut scheduled = Vec::with_capacity(ids.len());
+    for id in ids {
+        let entry = find_entry(params_dir.join("burn.json"), &id)?;
+        scheduled.push((entry_mint_after(&entry)?, id));
+    }
+    // Stable, so burns due at the same time keep their order
+    scheduled.sort_by_key(|(mint_after, _)| *mint_after);
+    Ok(scheduled.into_iter().map(|(_, id)| id).collect())
+}
 
-        let (
-            burn_key,
-            burn_addr,
-            _nullifier_fp,
-            nullifier_u256,
-            remaining_coin_val,
-            remaining_coin_u256,
-            burn_extra_commit,
-        ) = self
-            .common_opt
-            .prepare_inputs(amount, fee, spend, receiver_hook.clone().into())
-            .await?;
+/// Sends the requested burn and returns the ids of its records. With `--split`
+/// an amount above the mint cap becomes a burn group: several burns of random
+/// sizes, each with its own burn-key, whose fee and spend add up to the
+/// requested ones. The group id is the id of its first burn. Without `send`
+/// the burns are only stored and their payment requests printed.
+async fn send_burn(
+    common_opt: &CommonOpt,
+    params_dir: &Path,
+    opt: &BurnSendOpt,
+    send: bool,
+) -> Result<Vec<String>> {
+    let amount = parse_ether(&opt.amount)?;
+    let fee = parse_ether(&opt.fee)?;
+    let spend = parse_ether(&opt.spend)?;
+    let prover_fee = parse_ether(&opt.prover_fee)?;
+    if fee + prover_fee + spend > amount {
+        bail!("Sum of --fee, --prover-fee and --spend should be less than --amount!");
+    }
+    let receiver = opt
+        .receiver
+        .unwrap_or_else(|| common_opt.private_key.address());
+    let receiver_hook = opt
+        .hook_opt
+        .receiver_hook(common_opt, receiver, spend)
+        .await?;
 
-        let (_tx_hash, _ok) = self.common_opt.send_burn_tx(burn_addr, amount).await?;
-        self.common_opt.persist_burn_data(
+    let mint_cap = common_opt.mint_cap().await?;
+    let max_chunk = match &opt.max_chunk {
+        Some(max_chunk) => parse_ether(max_chunk)?.min(mint_cap),
+        None => mint_cap,
+    };
+    if !opt.split || amount <= max_chunk {
+        let commitment = BurnCommitment {
+            receiver,
+            receiver_hook,
+            broadcaster_fee: fee,
+            prover_fee,
+            spend,
+            prover: opt.prover,
+        };
+        let id = send_single_burn(
+            common_opt,
             params_dir,
-            burn_key,
-            remaining_coin_val,
-            Some(fee),
-            Some(spend),
-            false,
+            amount,
+            &commitment,
+            &opt.schedule_opt,
+            &opt.pow_opt,
+            send,
+        )
+        .await?;
+        return Ok(vec![id]);
+    }
+    if !receiver_hook.is_empty() {
+        bail!("A split burn can't have a post-mint hook!");
+    }
+    if !prover_fee.is_zero() || opt.prover.is_some() {
+        bail!("A split burn can't use an external prover!");
+    }
+
+    let chunks = split_burn(amount, fee, spend, max_chunk)?;
+    println!("Splitting the burn into {} burns:", chunks.len());
+    for (amount, fee, spend) in &chunks {
+        println!(
+            "  amount {} ETH, fee {} ETH, spend {} ETH",
+            format_ether(*amount),
+            format_ether(*fee),
+            format_ether(*spend)
+        );
+    }
+    let mut ids = Vec::new();
+    for (i, (amount, fee, spend)) in chunks.iter().enumerate() {
+        let commitment = BurnCommitment {
+            receiver,
+            receiver_hook: Bytes::new(),
+            broadcaster_fee: *fee,
+            prover_fee: U256::ZERO,
+            spend: *spend,
+            prover: None,
+        };
+        let (id, burn_addr) = store_single_burn(
+            common_opt,
+            params_dir,
+            *amount,
+            &commitment,
+            &opt.schedule_opt,
+            &opt.pow_opt,
+        )
+        .await?;
+        // Recorded before funding, so a chunk that fails to send still resumes
+        // with its group
+        let group = ids.first().unwrap_or(&id).clone();
+        update_entry(
+            params_dir.join("burn.json"),
+            &id,
+            json!({ "group": group, "groupIndex": i, "groupSize": chunks.len() }),
         )?;
-        println!("Your Burn address:{:?}", burn_addr);
-        let (proof, block_number, _out_json_path) = self
-            .common_opt
-            .build_and_prove_burn(
-                params_dir,
-                burn_addr,
-                burn_key,
-                spend,
-                burn_extra_commit,
-                "input.json",
-                "witness.wtns",
+        fund_single_burn(common_opt, params_dir, &id, burn_addr, *amount, send).await?;
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// Splits `amount` into chunks of at most `max_chunk`, with random sizes
+/// around their average so that the burns don't look alike. `fee` and `spend`
+/// are shared proportionally and the chunk amounts are rebuilt from the shares,
+/// so every chunk covers its own fee and spend and the totals are exact.
+fn split_burn(
+    amount: U256,
+    fee: U256,
+    spend: U256,
+    max_chunk: U256,
+) -> Result<Vec<(U256, U256, U256)>> {
+    // Rounding the shares may add up to 3 wei per chunk to the last one, so
+    // the chunks are kept that far below the cap
+    let mut n = 1;
+    let limit = loop {
+        let limit = max_chunk.saturating_sub(U256::from(3 * n));
+        if amount <= U256::from(n) * limit {
+            break limit;
+        }
+        n += 1;
+        if n > MAX_SPLIT_BURNS {
+            bail!(
+                "Splitting {} ETH in chunks of {} ETH needs more than {} burns!",
+                format_ether(amount),
+                format_ether(max_chunk),
+                MAX_SPLIT_BURNS
+            );
+        }
+    };
+
+    let mut targets = Vec::with_capacity(n);
+    let mut remaining = amount;
+    for i in 0..n {
+        let left = U256::from(n - i);
+        if left == U256::ONE {
+            targets.push(remaining);
+            break;
+        }
+        let average = remaining / left;
+        let lo = remaining
+            .saturating_sub((left - U256::ONE) * limit)
+            .max(average * U256::from(3) / U256::from(4));
+        let hi = limit.min(average * U256::from(5) / U256::from(4)).max(lo);
+        let target = lo + U256::from_be_bytes(B256::random().0) % (hi - lo + U256::ONE);
+        targets.push(target);
+        remaining -= target;
+    }
+
+    let unspent = amount - fee - spend;
+    let (mut fee_left, mut spend_left, mut unspent_left) = (fee, spend, unspent);
+    let mut chunks = Vec::with_capacity(n);
+    for (i, target) in targets.iter().enumerate() {
+        let (chunk_fee, chunk_spend, chunk_unspent) = if i + 1 == n {
+            (fee_left, spend_left, unspent_left)
+        } else {
+            (
+                fee * target / amount,
+                spend * target / amount,
+                unspent * target / amount,
             )
-            .await?;
+        };
+        fee_left -= chunk_fee;
+        spend_left -= chunk_spend;
+        unspent_left -= chunk_unspent;
+        chunks.push((
+            chunk_fee + chunk_spend + chunk_unspent,
+            chunk_fee,
+            chunk_spend,
+        ));
+    }
+    Ok(chunks)
+}
+
+/// Derives a burn-key, stores the burn record and sends the ETH. The record is
+/// written before the transfer so that a crash never loses the burn-key.
+/// Without `send` the record stays pending until the burn address is funded
+/// from elsewhere.
+async fn send_single_burn(
+    common_opt: &CommonOpt,
+    params_dir: &Path,
+    amount: U256,
+    commitment: &BurnCommitment,
+    schedule_opt: &MintScheduleOpt,
+    pow_opt: &PowOpt,
+    send: bool,
+) -> Result<String> {
+    let (id, burn_addr) = store_single_burn(
+        common_opt,
+        params_dir,
+        amount,
+        commitment,
+        schedule_opt,
+        pow_opt,
+    )
+    .await?;
+    fund_single_burn(common_opt, params_dir, &id, burn_addr, amount, send).await?;
+    Ok(id)
+}
 
-        self.common_opt
+/// Derives a burn-key and stores the burn record with its mint schedule.
+async fn store_single_burn(
+    common_opt: &CommonOpt,
+    params_dir: &Path,
+    amount: U256,
+    commitment: &BurnCommitment,
+    schedule_opt: &MintScheduleOpt,
+    pow_opt: &PowOpt,
+) -> Result<(String, Address)> {
+    let (burn_key, burn_addr, _, _, _, _, _) = common_opt
+        .prepare_inputs(amount, commitment, pow_opt)
+        .await?;
+
+    let id = common_opt.persist_burn_record(
+        params_dir,
+        burn_key,
+        amount,
+        burn_addr,
+        pow_opt.byte_security_relax,
+        commitment,
+    )?;
+    println!("Burn #{} stored. Your Burn address:{:?}", id, burn_addr);
+    if let Some(mint_after) = schedule_opt.mint_after() {
+        update_entry(
+            params_dir.join("burn.json"),
+            &id,
+            json!({ "mintAfter": mint_after.duration_since(UNIX_EPOCH)?.as_secs() }),
+        )?;
+        println!(
+            "Burn #{} will be proved and minted after {}",
+            id,
+            humantime::format_rfc3339_seconds(mint_after)
+        );
+    }
+    Ok((id, burn_addr))
+}
+
+/// Sends the ETH of a stored burn, or prints its payment request when it is
+/// funded from elsewhere.
+async fn fund_single_burn(
+    common_opt: &CommonOpt,
+    params_dir: &Path,
+    id: &str,
+    burn_addr: Address,
+    amount: U256,
+    send: bool,
+) -> Result<()> {
+    if !send {
+        update_entry(
+            params_dir.join("burn.json"),
+            id,
+            json!({ "funding": "external" }),
+        )?;
+        let rt = common_opt.setup().await?;
+        print_payment_request(burn_addr, amount, rt.provider.get_chain_id().await?)?;
+        return Ok(());
+    }
+
+    let burn_path = params_dir.join("burn.json");
+    let (tx_hash, ok) = common_opt
+        .send_burn_tx(params_dir, burn_addr, amount)
+        .await?;
+    update_entry(&burn_path, id, json!({ "txHash": tx_hash }))?;
+    if !ok {
+        bail!("Burn transaction {} failed!", tx_hash);
+    }
+    set_status(params_dir, id, BurnStatus::Sent)?;
+    Ok(())
+}
+
+/// Prints the EIP-681 URI paying `amount` to the burn address, and its QR code.
+fn print_payment_request(burn_addr: Address, amount: U256, chain_id: u64) -> Result<()> {
+    let uri = format!("ethereum:{}@{}?value={}", burn_addr, chain_id, amount);
+    let qr = QrCode::new(uri.as_bytes())?
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build();
+    println!("Send exactly {} ETH to {}", format_ether(amount), burn_addr);
+    println!("{}", uri);
+    println!("{}", qr);
+    Ok(())
+}
+
+/// Checks an amount to commit to other than the recorded one, e.g. to also
+/// mint an accidental top-up of its burn address or to leave part of it burnt.
+fn check_intended_amount(entry: &Value, id: &str, amount: U256, recorded: U256) -> Result<()> {
+    let (_, fee, spend) = burn_params(entry)?;
+    if fee + entry_prover_fee(entry)? + spend > amount {
+        bail!("The intended amount can't be less than the fees and the spend of the burn!");
+    }
+    if amount != recorded {
+        println!(
+            "Burn #{} commits to {} ETH instead of the recorded {} ETH",
+            id,
+            format_ether(amount),
+            format_ether(recorded)
+        );
+    }
+    Ok(())
+}
+
+/// Amount the burn was made for. Entries of older versions don't record it,
+/// but were stored right after the transfer, so the burn address holds it.
+async fn burn_amount(common_opt: &CommonOpt, entry: &Value, burn_addr: Address) -> Result<U256> {
+    if let Some(amount) = entry.get("amount").and_then(Value::as_str) {
+        return Ok(amount.parse()?);
+    }
+    let rt = common_opt.setup().await?;
+    let balance = rt.provider.get_balance(burn_addr).await?;
+    if balance.is_zero() {
+        bail!("Burn address {} holds no ETH", burn_addr);
+    }
+    Ok(balance)
+}
+
+/// Builds the proof-of-burn of a sent burn and stores it next to burn.json.
+/// The proof commits to `intended`, or to the amount of the burn if `None`,
+/// and credits `prover`, or the wallet if `None`.
+async fn prove_burn(
+    common_opt: &CommonOpt,
+    block_opt: &BlockOpt,
+    params_dir: &Path,
+    id: &str,
+    intended: Option<U256>,
+    prover: Option<Address>,
+) -> Result<()> {
+    let entry = find_entry(params_dir.join("burn.json"), id)?;
+    let status = BurnStatus::of(&entry)?;
+    if status == BurnStatus::Minted {
+        bail!("Burn #{} is already minted", id);
+    }
+    if status == BurnStatus::Pending {
+        // E.g. a prepared burn funded with less than requested, proved for
+        // what its address holds
+        check_funded(common_opt, &entry, id, intended).await?;
+        set_status(params_dir, id, BurnStatus::Sent)?;
+    }
+    // The burn address doesn't commit to the prover, but a prover fee is
+    // meant for the prover the burn was sent for
+    let wallet = common_opt.private_key.address();
+    let prover = prover.unwrap_or(wallet);
+    if let Some(stored) = stored_prover(&entry)?
+        && stored != prover
+        && !entry_prover_fee(&entry)?.is_zero()
+    {
+        bail!(
+            "Burn #{} pays its prover fee to {}. Store its proof with: worm-miner burn import-proof {} [proof.json]",
+            id,
+            stored,
+            id
+        );
+    }
+    let burn_key = entry_burn_key(&entry)?;
+    let commitment = BurnCommitment {
+        prover: Some(prover),
+        ..entry_commitment(&entry, wallet)?
+    };
+    let (burn_addr, burn_extra_commit) = commitment.burn_address(burn_key);
+    check_burn_commitment(
+        entry_burn_address(&entry)?,
+        entry_extra_commitment(&entry)?,
+        burn_addr,
+        burn_extra_commit,
+    )
+    .with_context(|| format!("Burn #{} can't be proved", id))?;
+    let recorded = burn_amount(common_opt, &entry, burn_addr).await?;
+    let amount = match intended {
+        Some(amount) => {
+            check_intended_amount(&entry, id, amount, recorded)?;
+            amount
+        }
+        None => recorded,
+    };
+
+    let (proof, block_number, _out_path) = common_opt
+        .build_and_prove_burn(
+            params_dir,
+            block_opt,
+            burn_key,
+            amount,
+            &commitment,
+            entry_byte_security_relax(&entry)?,
+        )
+        .await?;
+
+    let proof_path = burn_proof_path(params_dir, id);
+    if let Some(dir) = proof_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&proof_path, serde_json::to_string_pretty(&proof)?)?;
+    println!("Proof of burn #{} saved to {}", id, proof_path.display());
+
+    update_entry(
+        params_dir.join("burn.json"),
+        id,
+        json!({
+            "status": BurnStatus::Proved.as_str(),
+            "intendedAmount": amount.to_string(),
+            "prover": prover.to_string(),
+            "blockNumber": block_number,
+            "blockTag": block_opt.block_tag.as_str(),
+            "confirmations": block_opt.confirmations,
+            "proofPath": proof_path.display().to_string(),
+        }),
+    )
+}
+
+/// Checks a proof generated by an external prover against the burn record
+/// and stores it, so that `burn mint` can broadcast it.
+fn import_proof(params_dir: &Path, id: &str, path: &Path) -> Result<()> {
+    let entry = find_entry(params_dir.join("burn.json"), id)?;
+    let status = BurnStatus::of(&entry)?;
+    if status < BurnStatus::Sent {
+        bail!("Burn #{} has not been sent yet", id);
+    }
+    if status == BurnStatus::Minted {
+        bail!("Burn #{} is already minted", id);
+    }
+    let output: ProofOutput = serde_json::from_slice(
+        &std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?,
+    )
+    .with_context(|| format!("failed to parse {}", path.display()))?;
+
+    let (burn_key, fee, spend) = burn_params(&entry)?;
+    let amount: U256 = entry_str(&entry, "amount")?.parse()?;
+    let (_, nullifier_u256) = compute_nullifier(burn_key);
+    let (_, remaining_coin_u256) = compute_remaining_coin(burn_key, amount, spend)?;
+    let prover: Address = output.prover.parse()?;
+    if let Some(stored_prover) = stored_prover(&entry)?
+        && stored_prover != prover
+        && !entry_prover_fee(&entry)?.is_zero()
+    {
+        bail!("Proof was made by {}, not by {}", prover, stored_prover);
+    }
+    let checks = [
+        (
+            "burn address",
+            output.burn_address.parse::<Address>()?.to_string(),
+            entry_burn_address(&entry)?
+                .context("burnAddress not found")?
+                .to_string(),
+        ),
+        (
+            "nullifier",
+            output.nullifier_u256.clone(),
+            nullifier_u256.to_string(),
+        ),
+        (
+            "remaining coin",
+            output.remaining_coin.clone(),
+            remaining_coin_u256.to_string(),
+        ),
+        (
+            "revealed amount",
+            output.reveal_amount.clone(),
+            spend.to_string(),
+        ),
+        (
+            "broadcaster fee",
+            output.broadcaster_fee.clone(),
+            fee.to_string(),
+        ),
+        (
+            "prover fee",
+            output.prover_fee.clone(),
+            entry_prover_fee(&entry)?.to_string(),
+        ),
+    ];
+    for (name, got, expected) in checks {
+        if got != expected {
+            bail!(
+                "The proof has a different {} ({}) than burn #{} ({})",
+                name,
+                got,
+                id,
+                expected
+            );
+        }
+    }
+    let proof: RapidsnarkOutput = serde_json::from_value(output.proof)?;
+
+    let proof_path = burn_proof_path(params_dir, id);
+    if let Some(dir) = proof_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&proof_path, serde_json::to_string_pretty(&proof)?)?;
+    println!("Proof of burn #{} saved to {}", id, proof_path.display());
+
+    update_entry(
+        params_dir.join("burn.json"),
+        id,
+        json!({
+            "status": BurnStatus::Proved.as_str(),
+            "prover": prover.to_string(),
+            "blockNumber": output.block_number,
+            "proofPath": proof_path.display().to_string(),
+        }),
+    )
+}
+
+/// Broadcasts mintCoin for a proved burn and adds the remaining coin to
+/// coins.json. A mint that already landed on-chain is detected through
+/// `BETH.nullifiers`, so running this twice is harmless.
+async fn mint_burn(common_opt: &CommonOpt, params_dir: &Path, id: &str) -> Result<()> {
+    let entry = find_entry(params_dir.join("burn.json"), id)?;
+    let status = BurnStatus::of(&entry)?;
+    if status == BurnStatus::Minted {
+        println!("Burn #{} is already minted", id);
+        return Ok(());
+    }
+    if status < BurnStatus::Proved {
+        bail!("Burn #{} has not been proved yet", id);
+    }
+    let (burn_key, _, spend) = burn_params(&entry)?;
+    let amount = entry_intended_amount(&entry)?.context("amount not found in the burn object")?;
+    let block_number = entry
+        .get("blockNumber")
+        .and_then(Value::as_u64)
+        .context("blockNumber not found in the burn object")?;
+
+    let proof_path = burn_proof_path(params_dir, id);
+    let proof: RapidsnarkOutput = serde_json::from_slice(
+        &std::fs::read(&proof_path)
+            .with_context(|| format!("failed to read {}", proof_path.display()))?,
+    )?;
+
+    let (_nullifier_fp, nullifier_u256) = compute_nullifier(burn_key);
+    let (remaining_coin_val, remaining_coin_u256) =
+        compute_remaining_coin(burn_key, amount, spend)?;
+
+    let rt = common_opt.setup().await?;
+    let beth = BETH::new(rt.network.beth, rt.provider);
+    if beth.nullifiers(nullifier_u256).call().await? {
+        println!("Burn #{} has already been minted on-chain", id);
+    } else {
+        common_opt
             .broadcast_mint(
+                params_dir,
                 &proof,
                 block_number,
                 nullifier_u256,
                 remaining_coin_u256,
-                fee,
-                spend,
-                receiver_hook.into(),
+                &entry_commitment(&entry, common_opt.private_key.address())?,
             )
             .await?;
+    }
+    // Also after a mint whose receipt a previous run didn't get
+    common_opt
+        .persist_minted_coin(
+            params_dir,
+            burn_key,
+            remaining_coin_val,
+            remaining_coin_u256,
+        )
+        .await?;
+    set_status(params_dir, id, BurnStatus::Minted)
+}
+
+/// Runs whatever steps are still missing for the given burn.
+pub(crate) async fn resume_burn(
+    common_opt: &CommonOpt,
+    block_opt: &BlockOpt,
+    params_dir: &Path,
+    id: &str,
+) -> Result<()> {
+    let entry = find_entry(params_dir.join("burn.json"), id)?;
+    let mut status = BurnStatus::of(&entry)?;
+    if status == BurnStatus::Minted {
+        println!("Burn #{} is already minted", id);
+        return Ok(());
+    }
+    println!("Resuming burn #{} (status: {})", id, status.as_str());
+
+    let rt = common_opt.setup().await?;
+    if status == BurnStatus::Pending {
+        check_funded(common_opt, &entry, id, None).await?;
+        set_status(params_dir, id, BurnStatus::Sent)?;
+        status = BurnStatus::Sent;
+    }
+    // Proving only once the schedule is due keeps the proven header close to
+    // the mint
+    if let Some(mint_after) = entry_mint_after(&entry)?
+        && let Ok(remaining) = mint_after.duration_since(SystemTime::now())
+    {
+        println!(
+            "Burn #{} is scheduled for {}, waiting {}...",
+            id,
+            humantime::format_rfc3339_seconds(mint_after),
+            humantime::format_duration(Duration::from_secs(remaining.as_secs()))
+        );
+        tokio::time::sleep(remaining).await;
+    }
+    if status == BurnStatus::Sent {
+        // Burns stored by older versions were minted right away; skip them
+        // instead of spending minutes on a proof that can't be used.
+        let (burn_key, _, _) = burn_params(&entry)?;
+        let (_, nullifier_u256) = compute_nullifier(burn_key);
+        let beth = BETH::new(rt.network.beth, &rt.provider);
+        if beth.nullifiers(nullifier_u256).call().await? {
+            println!("Burn #{} has already been minted on-chain", id);
+            return set_status(params_dir, id, BurnStatus::Minted);
+        }
+        prove_burn(common_opt, block_opt, params_dir, id, None, None).await?;
+        status = BurnStatus::Proved;
+    }
+    if status == BurnStatus::Proved {
+        mint_burn(common_opt, params_dir, id).await?;
+    }
+    Ok(())
+}
+
+/// Checks that the burn address of a pending burn holds the amount to prove,
+/// `intended` or else the recorded one. The process may have died after
+/// broadcasting the transfer but before its receipt arrived, or the burn is
+/// funded from another wallet, so the burn address itself is looked at.
+async fn check_funded(
+    common_opt: &CommonOpt,
+    entry: &Value,
+    id: &str,
+    intended: Option<U256>,
+) -> Result<()> {
+    let rt = common_opt.setup().await?;
+    let burn_addr = entry_burn_address(entry)?.context("burnAddress not found")?;
+    let amount: U256 = entry_str(entry, "amount")?.parse()?;
+    let balance = rt.provider.get_balance(burn_addr).await?;
+    if balance.is_zero() {
+        if entry.get("funding").and_then(Value::as_str) == Some("external") {
+            bail!(
+                "Burn address {} of burn #{} is not funded yet, send it exactly {} ETH",
+                burn_addr,
+                id,
+                format_ether(amount)
+            );
+        }
+        bail!(
+            "Burn address {} of burn #{} holds no ETH, the transfer never landed",
+            burn_addr,
+            id
+        );
+    }
+    // Extra ETH is reported while proving, but a shortfall can't be proved
+    match intended {
+        Some(intended) if balance < intended => bail!(
+            "Burn address {} of burn #{} holds {} ETH, less than the intended {} ETH",
+            burn_addr,
+            id,
+            format_ether(balance),
+            format_ether(intended)
+        ),
+        None if balance < amount => bail!(
+            "Burn address {} of burn #{} holds {} ETH, but the burn was made for {} ETH. \
+             To mint what it holds, run: worm-miner burn prove {} --intended-amount {}",
+            burn_addr,
+            id,
+            format_ether(balance),
+            format_ether(amount),
+            id,
+            format_ether(balance)
+        ),
+        _ => Ok(()),
+    }
+}
+
+fn burn_params(entry: &Value) -> Result<(Fp, U256, U256)> {
+    let burn_key = entry_burn_key(entry)?;
+    let fee: U256 = entry_str(entry, "fee")?.parse()?;
+    let spend: U256 = entry_str(entry, "spend")?.parse()?;
+    Ok((burn_key, fee, spend))
+}
+
+/// Prover of the burn. Entries of older versions only name it once proved.
+fn stored_prover(entry: &Value) -> Result<Option<Address>> {
+    match entry.get("prover").and_then(Value::as_str) {
+        Some(addr) => Ok(Some(addr.parse()?)),
+        None => Ok(None),
+    }
+}
+
+fn set_status(params_dir: &Path, id: &str, status: BurnStatus) -> Result<()> {
+    update_entry(
+        params_dir.join("burn.json"),
+        id,
+        json!({ "status": status.as_str() }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eth(amount: &str) -> U256 {
+        parse_ether(amount).unwrap()
+    }
+
+    fn check_split(amount: U256, fee: U256, spend: U256, max_chunk: U256) {
+        let chunks = split_burn(amount, fee, spend, max_chunk).unwrap();
+        let (mut amounts, mut fees, mut spends) = (U256::ZERO, U256::ZERO, U256::ZERO);
+        for (chunk, chunk_fee, chunk_spend) in &chunks {
+            assert!(*chunk <= max_chunk, "chunk {} above the cap", chunk);
+            assert!(chunk_fee + chunk_spend <= *chunk);
+            amounts += chunk;
+            fees += chunk_fee;
+            spends += chunk_spend;
+        }
+        assert_eq!((amounts, fees, spends), (amount, fee, spend));
+    }
+
+    #[test]
+    fn split_burn_keeps_totals_and_cap() {
+        for _ in 0..20 {
+            check_split(eth("25"), eth("0.01"), eth("24"), eth("10"));
+            check_split(eth("7.5"), eth("0.3"), eth("1"), eth("1"));
+            check_split(eth("1"), U256::ZERO, U256::ZERO, eth("0.3"));
+        }
+    }
+
+    #[test]
+    fn split_burn_fits_an_exact_multiple_of_the_cap() {
+        // The cap leaves room for rounding, so 2 × cap takes a third burn
+        let chunks = split_burn(eth("2"), eth("0.1"), eth("1"), eth("1")).unwrap();
+        assert_eq!(chunks.len(), 3);
+        check_split(eth("2"), eth("0.1"), eth("1"), eth("1"));
+    }
+
+    #[test]
+    fn split_burn_refuses_too_many_burns() {
+        assert!(split_burn(eth("101"), U256::ZERO, U256::ZERO, eth("1")).is_err());
+    }
+
+    #[test]
+    fn mint_delay_parses_a_single_duration() {
+        let delay: MintDelay = "2h".parse().unwrap();
+        assert_eq!(delay.min, Duration::from_secs(2 * 3600));
+        assert_eq!(delay.max, delay.min);
+        assert_eq!(delay.sample(), delay.min);
+    }
+
+    #[test]
+    fn mint_delay_parses_a_range() {
+        let delay: MintDelay = "1h .. 3h".parse().unwrap();
+        assert_eq!(delay.min, Duration::from_secs(3600));
+        assert_eq!(delay.max, Duration::from_secs(3 * 3600));
+        for _ in 0..20 {
+            let sample = delay.sample();
+            assert!(delay.min <= sample && sample <= delay.max);
+        }
+    }
 
-        Ok(())
+    #[test]
+    fn mint_delay_rejects_empty_ranges_and_garbage() {
+        assert!("3h..1h".parse::<MintDelay>().is_err());
+        assert!("soon".parse::<MintDelay>().is_err());
+        assert!("1h..".parse::<MintDelay>().is_err());
     }
 }
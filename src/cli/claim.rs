@@ -17,20 +17,26 @@ pub struct ClaimOpt {
 }
 
 impl ClaimOpt {
-    pub async fn run(self) -> Result<(), anyhow::Error> {
+    pub async fn run(self, params_dir: &std::path::Path) -> Result<(), anyhow::Error> {
         let net = self.common_opt.overridden_network()?;
         let addr = self.common_opt.private_key.address();
+        let gas_opt = self.common_opt.gas_opt.clone();
         let provider = ProviderBuilder::new()
-            .wallet(self.common_opt.private_key)
+            .wallet(self.common_opt.private_key.clone())
             .connect_http(net.rpc.clone());
         let worm = WORM::new(net.worm, provider.clone());
         let epoch = worm.currentEpoch().call().await?;
         let num_epochs = std::cmp::min(epoch, U256::from(self.num_epochs as u64));
-        let receipt = worm
-            .claim(U256::from(self.from_epoch as u64), num_epochs)
-            .send()
+        let tx = gas_opt
+            .apply_to_call(
+                &provider,
+                worm.claim(U256::from(self.from_epoch as u64), num_epochs),
+            )
             .await?
-            .get_receipt()
+            .into_transaction_request();
+        let receipt = self
+            .common_opt
+            .send_tracked(params_dir, &provider, tx, "claim")
             .await?;
         if receipt.status() {
             println!("Success!");
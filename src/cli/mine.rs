@@ -23,22 +23,25 @@ pub struct MineOpt {
 }
 
 impl MineOpt {
-    pub async fn run(self) -> Result<(), anyhow::Error> {
+    pub async fn run(self, params_dir: &std::path::Path) -> Result<(), anyhow::Error> {
         let amount_per_epoch = parse_ether(&self.amount_per_epoch)?;
         let addr = self.common_opt.private_key.address();
         let net = self.common_opt.overridden_network()?;
+        let gas_opt = self.common_opt.gas_opt.clone();
         let provider = ProviderBuilder::new()
-            .wallet(self.common_opt.private_key)
+            .wallet(self.common_opt.private_key.clone())
             .connect_http(net.rpc.clone());
         let worm = WORM::new(net.worm, provider.clone());
         let beth = BETH::new(net.beth, provider.clone());
         if beth.allowance(addr, net.worm).call().await?.is_zero() {
             println!("Approving infinite BETH allowance to WORM contract...");
-            let beth_approve_receipt = beth
-                .approve(net.worm, U256::MAX)
-                .send()
+            let tx = gas_opt
+                .apply_to_call(&provider, beth.approve(net.worm, U256::MAX))
                 .await?
-                .get_receipt()
+                .into_transaction_request();
+            let beth_approve_receipt = self
+                .common_opt
+                .send_tracked(params_dir, &provider, tx, "approve")
                 .await?;
             if !beth_approve_receipt.status() {
                 panic!("Failed on BETH approval!");
@@ -71,11 +74,16 @@ impl MineOpt {
                         epoch,
                         epoch + U256::from(self.num_epochs)
                     );
-                    let receipt = worm
-                        .participate(amount_per_epoch, U256::from(self.num_epochs as u64))
-                        .send()
+                    let tx = gas_opt
+                        .apply_to_call(
+                            &provider,
+                            worm.participate(amount_per_epoch, U256::from(self.num_epochs as u64)),
+                        )
                         .await?
-                        .get_receipt()
+                        .into_transaction_request();
+                    let receipt = self
+                        .common_opt
+                        .send_tracked(params_dir, &provider, tx, "participate")
                         .await?;
                     if receipt.status() {
                         println!("Success!");
@@ -85,14 +93,19 @@ impl MineOpt {
                         && !claimable_worm.is_zero()
                     {
                         println!("Claiming WORMs...");
-                        let receipt = worm
-                            .claim(
-                                epoch.saturating_sub(num_epochs_to_check),
-                                num_epochs_to_check,
+                        let tx = gas_opt
+                            .apply_to_call(
+                                &provider,
+                                worm.claim(
+                                    epoch.saturating_sub(num_epochs_to_check),
+                                    num_epochs_to_check,
+                                ),
                             )
-                            .send()
                             .await?
-                            .get_receipt()
+                            .into_transaction_request();
+                        let receipt = self
+                            .common_opt
+                            .send_tracked(params_dir, &provider, tx, "claim")
                             .await?;
                         if receipt.status() {
                             println!("Success!");
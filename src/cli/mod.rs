@@ -5,26 +5,37 @@ mod info;
 mod ls;
 mod mine;
 mod participate;
+mod pow_bench;
 mod recover;
+mod relay;
 mod spend;
+mod spend_batch;
+mod tx;
 mod utils;
-use crate::cli::utils::{append_new_entry, burn_file, coins_file, init_coins_file, next_id};
-use crate::constants::poseidon_burn_address_prefix;
+mod watch;
+use crate::cli::utils::{
+    append_new_entry, burn_file, coins_file, init_coins_file, next_id, read_entries,
+};
+use crate::decode::describe_call_error;
 use crate::fp::Fp;
-pub use recover::RecoverOpt;
-use crate::utils::{RapidsnarkOutput, build_and_prove_burn_logic, generate_burn_extra_commit};
+use crate::hooks::{self, HookContext};
+use crate::pow::{POW_MIN_ZERO_BYTES, default_threads, find_burn_key_cancellable};
+use crate::public_inputs::{
+    BurnPublicInputs, PublicInputs, SpendPublicInputs, check_public_inputs,
+};
+use crate::server::types::{MintRequest, SpendRequest};
+use crate::utils::ProvingBlockTag;
+use crate::utils::{BurnCommitment, RapidsnarkOutput, build_and_prove_burn_logic};
 use crate::utils::{
-    compute_nullifier, compute_previous_coin, compute_remaining_coin, fetch_block_and_header_bytes,
-    find_burn_key, generate_burn_address, get_account_proof,
+    check_burn_unminted, compute_nullifier, compute_previous_coin, compute_remaining_coin,
+    fetch_block_and_header_bytes, get_account_proof, wait_for_proving_block,
 };
 use alloy::consensus::Receipt;
-use alloy::primitives::{Bytes, U160, address};
+use alloy::contract::{CallBuilder, CallDecoder};
+use alloy::primitives::Bytes;
 use alloy::signers::local::PrivateKeySigner;
-use alloy::sol_types::{SolCall, SolValue};
 use alloy::{
-    hex::ToHexExt,
-    network::TransactionBuilder,
-    primitives::utils::{format_ether, parse_ether},
+    hex::ToHexExt, network::TransactionBuilder, primitives::utils::format_ether,
     rpc::types::TransactionRequest,
 };
 use alloy::{
@@ -33,6 +44,7 @@ use alloy::{
 };
 use anyhow::anyhow;
 use ff::PrimeField;
+pub use recover::RecoverOpt;
 use reqwest::Url;
 use serde_json::json;
 use std::path::PathBuf;
@@ -48,60 +60,97 @@ pub struct CommonOpt {
     private_key: PrivateKeySigner,
     #[structopt(long)]
     custom_rpc: Option<Url>,
+    #[structopt(flatten)]
+    gas_opt: GasOpt,
+    /// Seconds to wait for a transaction receipt before giving up (0 waits forever)
+    #[structopt(long, default_value = "300")]
+    receipt_timeout: u64,
+    /// Broadcast mintCoin/spendCoin even if their simulation reverts (requires --gas-limit)
+    #[structopt(long)]
+    force: bool,
+    /// Broadcast mintCoin/spendCoin even if their proof commits to other values
+    #[structopt(long)]
+    skip_proof_check: bool,
+    /// Hand mintCoin/spendCoin to this broadcaster (a `worm-miner server` in
+    /// relayer mode) instead of sending them from the wallet, which then pays
+    /// no gas. The broadcaster keeps the fee
+    #[structopt(long)]
+    relayer: Option<Url>,
 }
 use crate::utils::BETH;
 use std::path::Path;
 
-#[derive(Debug)]
-pub struct RuntimeContext<P: Provider> {
-    pub network: Network,
-    pub wallet_address: Address,
-    pub provider: P,
+#[derive(StructOpt)]
+pub struct BlockOpt {
+    /// Number of blocks that must be built on top of the block being proven
+    #[structopt(long, default_value = "0")]
+    confirmations: u64,
+    /// Block to prove against: latest, safe or finalized
+    #[structopt(long, default_value = "latest")]
+    block_tag: ProvingBlockTag,
 }
 
-use alloy::dyn_abi::DynSolValue;
-use alloy::primitives::I256;
-use alloy::{hex, sol};
-
-sol! {
-    interface IUniswapV3Pool {
-        /// swap function
-        function swap(
-            address recipient,
-            bool zeroForOne,
-            int256 amountSpecified,
-            uint160 sqrtPriceLimitX96,
-            bytes calldata data
-        ) external returns (int256 amount0, int256 amount1);
-    }
+#[derive(StructOpt)]
+pub struct HookOpt {
+    /// Post-mint hook run on the minted BETH: swap:<amount>[,slippage=1%],
+    /// transfer:<amount>,to=<address> or participate:<amount>,epochs=<n>,via=<relay>
+    #[structopt(long)]
+    hook: Option<String>,
+    /// Raw calldata of the post-mint hook, as hex
+    #[structopt(long)]
+    receiver_hook: Option<Bytes>,
+    /// Swap this amount of the minted BETH to ETH on Uniswap right after minting
+    /// (same as --hook swap:<amount>)
+    #[structopt(long)]
+    swap_to_eth: Option<String>,
 }
 
-/// Generate calldata for Uniswap V3 pool swap (BETH -> ETH)
-fn get_swap_calldata(amount_in: U256, recipient: Address) -> Vec<u8> {
-    let zero_for_one = false;
-    let amount_specified = I256::from_raw(amount_in);
-
-    let sqrt_price_limit_x96 = if zero_for_one {
-        U160::from_str_radix("4295128741", 10).unwrap()
-    } else {
-        U160::from_str_radix("1461446703485210103287273052203988822378723970340", 10).unwrap()
-    };
-    let data = Vec::new();
-    let swap_call = IUniswapV3Pool::swapCall {
-        recipient,
-        zeroForOne: zero_for_one,
-        amountSpecified: amount_specified,
-        sqrtPriceLimitX96: sqrt_price_limit_x96,
-        data: data.into(),
+impl HookOpt {
+    /// Encodes the requested post-mint hook, or an empty one if none was given.
+    pub async fn receiver_hook(
+        &self,
+        common_opt: &CommonOpt,
+        receiver: Address,
+        spend: U256,
+    ) -> Result<Bytes> {
+        let spec = match (&self.hook, &self.receiver_hook, &self.swap_to_eth) {
+            (None, None, None) => return Ok(Bytes::new()),
+            (None, Some(hook), None) => return Ok(hook.clone()),
+            (Some(spec), None, None) => spec.clone(),
+            (None, None, Some(amount)) => format!("swap:{}", amount),
+            _ => {
+                return Err(anyhow!(
+                    "Only one of --hook, --receiver-hook and --swap-to-eth can be given!"
+                ));
+            }
+        };
+        let network = common_opt.overridden_network()?;
+        let ctx = HookContext {
+            provider: ProviderBuilder::new()
+                .connect_http(network.rpc.clone())
+                .erased(),
+            receiver,
+            spend,
+        };
+        hooks::encode(&spec, &ctx).await
     }
-    .abi_encode();
-
-    (
-        address!("0x646b5eB499411390448b5e21838aCB8B2FF548dA"),
-        amount_in,
-        swap_call,
-    )
-        .abi_encode_params()
+}
+
+#[derive(StructOpt)]
+pub struct PowOpt {
+    /// Zero bytes of PoW traded for address-hash security (circuit's byteSecurityRelax)
+    #[structopt(long, default_value = "0")]
+    byte_security_relax: u8,
+    /// Threads used for the burn-key search (default: all cores)
+    #[structopt(long)]
+    pow_threads: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct RuntimeContext<P: Provider> {
+    pub network: Network,
+    pub wallet_address: Address,
+    pub provider: P,
 }
 
 impl CommonOpt {
@@ -137,115 +186,153 @@ impl CommonOpt {
         })
     }
 
+    /// Runs `call` through eth_call and returns the gas limit to send it with.
+    /// A reverting call is refused, with its decoded revert reason, unless
+    /// `--force` is given.
+    async fn simulate<P: Provider, D: CallDecoder>(
+        &self,
+        call: &CallBuilder<P, D>,
+        name: &str,
+    ) -> Result<u64> {
+        println!("Simulating {}...", name);
+        let result = match call.call_raw().await {
+            Ok(_) => call.estimate_gas().await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(estimated_gas) => {
+                println!("Simulation succeeded, estimated gas: {}", estimated_gas);
+                Ok(self.gas_opt.gas_limit().unwrap_or(estimated_gas))
+            }
+            Err(err) => {
+                let reason = describe_call_error(&err);
+                if !self.force {
+                    return Err(anyhow!(
+                        "{} would revert: {} (pass --force to broadcast it anyway)",
+                        name,
+                        reason
+                    ));
+                }
+                println!("{} would revert: {}, broadcasting anyway!", name, reason);
+                self.gas_opt.gas_limit().ok_or(anyhow!(
+                    "Gas can't be estimated for a reverting call, --force requires --gas-limit!"
+                ))
+            }
+        }
+    }
+
     pub async fn broadcast_mint(
         &self,
+        params_dir: &Path,
         proof: &RapidsnarkOutput,
         block_number: u64,
         nullifier: U256,
         remaining_coin: U256,
-        fee: U256,
-        spend: U256,
-        swap_calldata: Bytes,
+        commitment: &BurnCommitment,
     ) -> Result<()> {
-        let rt = self.setup().await?; // get provider, wallet, network from self
-        println!("Broadcasting mint transaction...");
-        let net = &rt.network;
-        // instantiate your BETH binding
-        let beth = BETH::new(net.beth, rt.provider);
-
-        // call the zk-proof mintCoin(...) method
-        let pending_tx = beth
-            .mintCoin(
-                // pi_a
-                [proof.proof.pi_a[0], proof.proof.pi_a[1]],
-                // pi_b (flipped coordinates)
-                [
-                    [proof.proof.pi_b[0][1], proof.proof.pi_b[0][0]],
-                    [proof.proof.pi_b[1][1], proof.proof.pi_b[1][0]],
-                ],
-                // pi_c
-                [proof.proof.pi_c[0], proof.proof.pi_c[1]],
-                // block number as U256
-                U256::from(block_number),
-                // nullifier & remaining_coin
-                nullifier,
-                remaining_coin,
-                // fee & spend
-                fee,
-                spend,
-                // user’s address
-                rt.wallet_address,
-                U256::ZERO,
-                rt.wallet_address,
-                swap_calldata,
-                Bytes::new(),
-            )
-            .send()
-            .await;
-        match pending_tx {
-            Ok(pending) => {
-                // transaction mined successfully
-                let receipt = pending.get_receipt().await?;
-                if receipt.status() {
-                    println!("Success!");
-                } else {
-                    println!("Transaction failed!");
-                }
-            }
-            Err(err) => {
-                // transaction reverted, err may contain revert data
-                if let Some(revert_bytes) = err.as_revert_data() {
-                    // revert_bytes: Vec<u8> — ABI encoded
-                    println!("Revert data (raw): 0x{}", hex::encode(revert_bytes.clone()));
-
-                    // decode revert reason as string
-                    if let Ok(reason) =
-                        ethers::abi::decode(&[ethers::abi::ParamType::String], &revert_bytes)
-                    {
-                        println!("Revert reason: {}", reason[0].to_string());
-                    }
-                } else {
-                    println!("Transaction failed without revert data: {:?}", err);
-                }
+        let request = MintRequest {
+            network: self.network.clone(),
+            proof: proof.clone(),
+            block_number,
+            nullifier,
+            remaining_coin,
+            broadcaster_fee: commitment.broadcaster_fee,
+            revealed_amount: commitment.spend,
+            revealed_amount_receiver: commitment.receiver,
+            prover_fee: commitment.prover_fee,
+            prover: commitment
+                .prover
+                .ok_or(anyhow!("The burn has no prover, prove it first"))?,
+            receiver_post_mint_hook: commitment.receiver_hook.clone(),
+            broadcaster_fee_post_mint_hook: Bytes::new(),
+        };
+        let net = self.overridden_network()?;
+        let block = ProviderBuilder::new()
+            .connect_http(net.rpc.clone())
+            .get_block_by_number(block_number.into())
+            .await?
+            .ok_or_else(|| anyhow!("Block #{} not found", block_number))?;
+        self.check_proof(
+            proof,
+            &BurnPublicInputs::of_mint(&request, block.header.hash),
+        )?;
+        let receipt = match &self.relayer {
+            Some(relayer) => self.relay(relayer, "mint", &request).await?,
+            None => {
+                let rt = self.setup().await?;
+                println!("Broadcasting mint transaction...");
+                let beth = BETH::new(net.beth, &rt.provider);
+                let call = beth.call_builder(&request.mint_call());
+                let gas_limit = self.simulate(&call, "mintCoin").await?;
+                let tx = self
+                    .gas_opt
+                    .apply_to_call_with_gas_limit(&rt.provider, call, gas_limit)
+                    .await?
+                    .into_transaction_request();
+                self.send_tracked(params_dir, &rt.provider, tx, "mint")
+                    .await?
             }
+        };
+        if !receipt.status() {
+            return Err(anyhow!("Mint transaction failed"));
         }
-
+        // Failed post-mint hooks were already reported along with the receipt
+        println!("Success!");
         Ok(())
     }
+    /// Aborts if the proof doesn't commit to the values about to be sent,
+    /// unless `--skip-proof-check` is given.
+    fn check_proof<T: PublicInputs>(&self, proof: &RapidsnarkOutput, expected: &T) -> Result<()> {
+        match check_public_inputs(proof, expected) {
+            Err(e) if self.skip_proof_check => {
+                println!("{}\nBroadcasting anyway (--skip-proof-check)", e);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
     pub async fn broadcast_spend(
         &self,
+        params_dir: &Path,
         proof: &crate::utils::RapidsnarkOutput,
         previous_coin: U256,
         out_amount: U256,
         remaining_coin: U256,
         fee: U256,
         receiver: alloy::primitives::Address,
-    ) -> anyhow::Result<()> {
-        let rt = self.setup().await?;
-        let beth = crate::utils::BETH::new(rt.network.beth, rt.provider);
-        let receipt = beth
-            .spendCoin(
-                [proof.proof.pi_a[0], proof.proof.pi_a[1]],
-                [
-                    [proof.proof.pi_b[0][1], proof.proof.pi_b[0][0]],
-                    [proof.proof.pi_b[1][1], proof.proof.pi_b[1][0]],
-                ],
-                [proof.proof.pi_c[0], proof.proof.pi_c[1]],
-                previous_coin,
-                out_amount,
-                remaining_coin,
-                fee,
-                receiver,
-            )
-            .send()
-            .await?
-            .get_receipt()
-            .await?;
+    ) -> anyhow::Result<String> {
+        let request = SpendRequest {
+            network: self.network.clone(),
+            proof: proof.clone(),
+            coin: previous_coin,
+            revealed_amount: out_amount,
+            remaining_coin,
+            broadcaster_fee: fee,
+            receiver,
+        };
+        self.check_proof(proof, &SpendPublicInputs::of_spend(&request))?;
+        let receipt = match &self.relayer {
+            Some(relayer) => self.relay(relayer, "spend", &request).await?,
+            None => {
+                let rt = self.setup().await?;
+                let beth = BETH::new(rt.network.beth, &rt.provider);
+                let call = beth.call_builder(&request.spend_call());
+                let gas_limit = self.simulate(&call, "spendCoin").await?;
+                let tx = self
+                    .gas_opt
+                    .apply_to_call_with_gas_limit(&rt.provider, call, gas_limit)
+                    .await?
+                    .into_transaction_request();
+                self.send_tracked(params_dir, &rt.provider, tx, "spend")
+                    .await?
+            }
+        };
         if !receipt.status() {
             return Err(anyhow::anyhow!("Spend transaction failed"));
         }
         println!("✓ Spend transaction successful!");
-        Ok(())
+        Ok(receipt.transaction_hash.to_string())
     }
 
     pub async fn write_spend_input_json<P: AsRef<Path>>(
@@ -275,29 +362,42 @@ impl CommonOpt {
         Ok(())
     }
 
+    /// Largest amount a single mintCoin accepts, read from BETH.MINT_CAP.
+    pub async fn mint_cap(&self) -> Result<U256> {
+        let rt = self.setup().await?;
+        let beth = BETH::new(rt.network.beth, &rt.provider);
+        Ok(beth.MINT_CAP().call().await?)
+    }
+
     pub async fn prepare_inputs(
         &self,
         amount: U256,
-        fee: U256,
-        spend: U256,
-        receiver_hook: Bytes,
+        commitment: &BurnCommitment,
+        pow_opt: &PowOpt,
     ) -> Result<(Fp, Address, Fp, U256, Fp, U256, U256)> {
-        let rt = self.setup().await?;
-
-        if fee + spend > amount {
+        let spend = commitment.spend;
+        if commitment.broadcaster_fee + commitment.prover_fee + spend > amount {
             return Err(anyhow!(
-                "Sum of --fee and --spend should be less than --amount!"
+                "Sum of --fee, --prover-fee and --spend should be less than --amount!"
             ));
         }
-        if amount > parse_ether("10")? {
-            return Err(anyhow!("Can't burn more than 10 ETH in a single call!"));
+        let mint_cap = self.mint_cap().await?;
+        if amount > mint_cap {
+            return Err(anyhow!(
+                "Can't burn more than BETH.MINT_CAP ({} ETH) in a single call! Pass --split to spread the burn over several burn addresses",
+                format_ether(mint_cap)
+            ));
         }
 
         // 1) burn_key
         println!("Generating a burn-key...");
-        let extra_commit =
-            generate_burn_extra_commit(rt.wallet_address, U256::ZERO, fee, receiver_hook.clone());
-        let burn_key = find_burn_key(2, extra_commit, spend);
+        let burn_key = find_burn_key_cancellable(
+            POW_MIN_ZERO_BYTES + pow_opt.byte_security_relax as usize,
+            commitment.extra_commitment(),
+            spend,
+            pow_opt.pow_threads.unwrap_or_else(default_threads),
+        )
+        .await?;
         println!("Your burn_key: {:?}", burn_key);
         println!(
             "Your burn-key as string: {}",
@@ -305,16 +405,7 @@ impl CommonOpt {
         );
 
         // 2) burn address
-        let burn_addr_prefix = poseidon_burn_address_prefix();
-        let (burn_addr, burn_extra_commit) = generate_burn_address(
-            burn_addr_prefix,
-            burn_key,
-            rt.wallet_address,
-            U256::ZERO,
-            fee,
-            spend,
-            receiver_hook.clone(),
-        );
+        let (burn_addr, burn_extra_commit) = commitment.burn_address(burn_key);
 
         // 3) nullifier (Fp only needed by caller)
         let (nullifier_fp, nullifier_u256) = compute_nullifier(burn_key);
@@ -337,18 +428,19 @@ impl CommonOpt {
     pub async fn recover_prepare_from_key(
         &self,
         burn_key: Fp,
+        receiver: Address,
+        prover_fee: U256,
         fee: U256,
         reveal: U256,
         receiver_hook: Bytes,
     ) -> anyhow::Result<(alloy::primitives::Address, Fp, U256)> {
-        let rt = self.setup().await?; // wallet addr + provider + network
         let burn_addr_prefix = crate::constants::poseidon_burn_address_prefix();
 
         let (burn_addr, _burn_extra_commit) = crate::utils::generate_burn_address(
             burn_addr_prefix,
             burn_key,
-            rt.wallet_address,
-            U256::ZERO,
+            receiver,
+            prover_fee,
             fee,
             reveal,
             receiver_hook,
@@ -360,24 +452,24 @@ impl CommonOpt {
     /// 2) Send ETH to burn address & check the receipt
     pub async fn send_burn_tx(
         &self,
+        params_dir: &Path,
         to_burn_addr: Address,
         amount: U256,
     ) -> Result<(String, bool)> {
         let rt = self.setup().await?;
 
-        let nonce = rt.provider.get_transaction_count(rt.wallet_address).await?;
+        // send_tracked picks the nonce from the pending count
         let tx = TransactionRequest::default()
+            .with_from(rt.wallet_address)
             .with_to(to_burn_addr)
-            .with_nonce(nonce)
             .with_chain_id(rt.provider.get_chain_id().await?)
-            .with_value(amount)
-            .with_gas_limit(21_000)
-            .with_max_priority_fee_per_gas(1_000_000_000)
-            .with_max_fee_per_gas(20_000_000_000);
-
-        let pending_tx = rt.provider.send_transaction(tx).await?;
-        let tx_hash = pending_tx.tx_hash().encode_hex();
-        let receipt = pending_tx.get_receipt().await?;
+            .with_value(amount);
+        let tx = self.gas_opt.apply(&rt.provider, tx).await?;
+
+        let receipt = self
+            .send_tracked(params_dir, &rt.provider, tx, "burn")
+            .await?;
+        let tx_hash = receipt.transaction_hash.encode_hex();
         if receipt.status() {
             println!(
                 "Successfully burnt {} ETH! Tx-hash: {}",
@@ -391,30 +483,54 @@ impl CommonOpt {
         }
     }
 
-    /// 3) Build input.json, generate witness, run rapidsnark
+    /// 3) Build input.json, generate witness, run rapidsnark.
+    ///
+    /// The proof commits to `intended_balance`, which may be less than what
+    /// the burn address actually holds.
     pub async fn build_and_prove_burn(
         &self,
         params_dir: &Path,
-        burn_addr: Address,
+        block_opt: &BlockOpt,
         burn_key: Fp,
-        spend: U256,
-        burn_extra_commit: U256,
-        input_json_path: &str,
-        witness_path: &str,
+        intended_balance: U256,
+        commitment: &BurnCommitment,
+        byte_security_relax: u8,
     ) -> Result<(RapidsnarkOutput, u64, PathBuf)> {
         let rt = self.setup().await?;
+        let (burn_addr, burn_extra_commit) = commitment.burn_address(burn_key);
+
+        // Fail before waiting for the proving block and running the prover
+        check_burn_unminted(&rt.provider, rt.network.beth, burn_key).await?;
+        let balance = rt.provider.get_balance(burn_addr).await?;
+        if !balance.is_zero() && balance < intended_balance {
+            report_balance_difference(balance, intended_balance)?;
+        }
 
-        let (block_number, header_bytes) = fetch_block_and_header_bytes(&rt.provider, None).await?;
-        let account_proof = get_account_proof(&rt.provider, burn_addr).await?;
+        let block_number = wait_for_proving_block(
+            &rt.provider,
+            burn_addr,
+            block_opt.block_tag,
+            block_opt.confirmations,
+        )
+        .await?;
+        println!("Proving against block #{}", block_number);
+        let (block_number, header_bytes) =
+            fetch_block_and_header_bytes(&rt.provider, Some(block_number)).await?;
+        let account_proof = get_account_proof(&rt.provider, burn_addr, block_number).await?;
+        report_balance_difference(account_proof.balance, intended_balance)?;
         let (proof, out_path) = build_and_prove_burn_logic(
             params_dir,
             header_bytes,
             burn_key,
-            spend,
+            intended_balance,
+            commitment.spend,
             burn_extra_commit,
-            rt.wallet_address,
-            input_json_path,
-            witness_path,
+            byte_security_relax,
+            commitment
+                .prover
+                .ok_or(anyhow!("The proof needs a prover to commit to"))?,
+            "input.json",
+            "witness.wtns",
             account_proof,
         )
         .await?;
@@ -422,32 +538,39 @@ impl CommonOpt {
         Ok((proof, block_number, out_path))
     }
 
-    pub fn persist_burn_data(
+    /// Stores a new entry in burn.json before any ETH is sent, so the burn-key
+    /// can never get lost. Returns the id of the new entry.
+    pub fn persist_burn_record(
         &self,
         params_dir: &Path,
         burn_key: Fp,
-        remaining_coin_val: Fp,
-        fee: Option<U256>,
-        spend: Option<U256>,
-        coins_only: bool,
-    ) -> Result<()> {
-        if !coins_only {
-            let (fee, spend) = match (fee, spend) {
-                (Some(f), Some(s)) => (f, s),
-                _ => {
-                    return Err(anyhow!(
-                        "fee and spend are required when coins_only == false"
-                    ));
-                }
-            };
-            let burn_path = params_dir.join("burn.json");
-            init_coins_file(&burn_path)?;
-            let burn_id = next_id(&burn_path)?;
-            let new_burn = burn_file(burn_id, burn_key, fee, &self.network, spend)?;
-            append_new_entry(&burn_path, new_burn)?;
-        }
+        amount: U256,
+        burn_addr: Address,
+        byte_security_relax: u8,
+        commitment: &BurnCommitment,
+    ) -> Result<String> {
+        let burn_path = params_dir.join("burn.json");
+        init_coins_file(&burn_path)?;
+        let burn_id = next_id(&burn_path)?;
+        let new_burn = burn_file(
+            burn_id,
+            burn_key,
+            &self.network,
+            amount,
+            burn_addr,
+            byte_security_relax,
+            commitment,
+        )?;
+        append_new_entry(&burn_path, new_burn)?;
+        Ok(burn_id.to_string())
+    }
 
-        // Always write coins.json
+    pub fn persist_coin(
+        &self,
+        params_dir: &Path,
+        burn_key: Fp,
+        remaining_coin_val: Fp,
+    ) -> Result<String> {
         let coins_path = params_dir.join("coins.json");
         println!("Generating coins.json file at: {}", coins_path.display());
         init_coins_file(&coins_path)?;
@@ -458,26 +581,69 @@ impl CommonOpt {
         append_new_entry(&coins_path, new_coin)?;
         println!("New coin entry added");
 
+        Ok(coin_id.to_string())
+    }
+
+    /// Adds the remaining coin of a minted burn to coins.json unless it is
+    /// there already. A mint whose receipt never arrived is only noticed later
+    /// through its spent nullifier, so the coin is looked up in `BETH.coins`.
+    pub async fn persist_minted_coin(
+        &self,
+        params_dir: &Path,
+        burn_key: Fp,
+        remaining_coin_val: Fp,
+        remaining_coin: U256,
+    ) -> Result<()> {
+        let burn_key_str = U256::from_le_bytes(burn_key.to_repr().0).to_string();
+        let amount = U256::from_le_bytes(remaining_coin_val.to_repr().0).to_string();
+        let stored = read_entries(params_dir.join("coins.json"))?
+            .iter()
+            .any(|coin| {
+                coin.get("network").and_then(|v| v.as_str()) == Some(self.network.as_str())
+                    && coin.get("burnKey").and_then(|v| v.as_str()) == Some(burn_key_str.as_str())
+                    && coin.get("amount").and_then(|v| v.as_str()) == Some(amount.as_str())
+            });
+        if stored {
+            return Ok(());
+        }
+        let rt = self.setup().await?;
+        if BETH::new(rt.network.beth, &rt.provider)
+            .coins(remaining_coin)
+            .call()
+            .await?
+            .is_zero()
+        {
+            println!("The remaining coin is not in BETH, coins.json is left as is");
+            return Ok(());
+        }
+        self.persist_coin(params_dir, burn_key, remaining_coin_val)?;
         Ok(())
     }
 
+    /// Returns the remaining coin of a recovered burn together with the
+    /// amount it commits to: `intended` if given, else the current balance.
     pub async fn recover_check_balance_and_compute_remaining(
         &self,
         burn_addr: alloy::primitives::Address,
         burn_key: Fp,
         fee: U256,
         spend: U256,
-    ) -> anyhow::Result<(Fp, U256)> {
+        intended: Option<U256>,
+    ) -> anyhow::Result<(Fp, U256, U256)> {
         let rt = self.setup().await?;
         let balance = rt.provider.get_balance(burn_addr).await?;
         if balance.is_zero() {
-            // Redundant safety (ensure_* should already panic), but keep guard here too
-            panic!("No ETH is present in the burn address!");
+            return Err(anyhow!(
+                "No ETH is present in the burn address {}!",
+                burn_addr
+            ));
         }
+        let intended = intended.unwrap_or(balance);
+        report_balance_difference(balance, intended)?;
 
         let (_remaining_fp, remaining_coin_u256) =
-            compute_remaining_coin(burn_key, balance, spend)?;
-        Ok((_remaining_fp, remaining_coin_u256))
+            compute_remaining_coin(burn_key, intended, spend)?;
+        Ok((_remaining_fp, remaining_coin_u256, intended))
     }
 
     pub fn spend_prepare_from_coin(
@@ -586,6 +752,29 @@ impl CommonOpt {
     }
 }
 
+/// Tells the user when the burn address doesn't hold exactly the amount the
+/// proof is going to commit to, and fails if it holds less.
+fn report_balance_difference(balance: U256, intended: U256) -> Result<()> {
+    if balance < intended {
+        return Err(anyhow!(
+            "The burn address holds {} ETH, {} ETH less than the intended {} ETH!",
+            format_ether(balance),
+            format_ether(intended - balance),
+            format_ether(intended)
+        ));
+    }
+    if balance > intended {
+        println!(
+            "The burn address holds {} ETH. Only the intended {} ETH is minted, the other {} ETH stays burnt!",
+            format_ether(balance),
+            format_ether(intended),
+            format_ether(balance - intended)
+        );
+    }
+    Ok(())
+}
+
+use crate::gas::GasOpt;
 use crate::networks::{NETWORKS, Network};
 pub use burn::BurnOpt;
 pub use claim::ClaimOpt;
@@ -595,4 +784,7 @@ pub use ls::LsCommand;
 pub use ls::LsOpt;
 pub use mine::MineOpt;
 pub use participate::ParticipateOpt;
-pub use spend::SpendOpt;
+pub use pow_bench::PowBenchOpt;
+pub use spend::SpendCommand;
+pub use tx::TxCommand;
+pub use watch::WatchBurnsOpt;
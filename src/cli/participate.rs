@@ -17,11 +17,12 @@ pub struct ParticipateOpt {
 }
 
 impl ParticipateOpt {
-    pub async fn run(self) -> Result<(), anyhow::Error> {
+    pub async fn run(self, params_dir: &std::path::Path) -> Result<(), anyhow::Error> {
         let net = self.common_opt.overridden_network()?;
         let addr = self.common_opt.private_key.address();
+        let gas_opt = self.common_opt.gas_opt.clone();
         let provider = ProviderBuilder::new()
-            .wallet(self.common_opt.private_key)
+            .wallet(self.common_opt.private_key.clone())
             .connect_http(net.rpc.clone());
         let amount_per_epoch = parse_ether(&self.amount_per_epoch)?;
         let worm = WORM::new(net.worm, provider.clone());
@@ -31,21 +32,28 @@ impl ParticipateOpt {
         let allowance = beth.allowance(addr, net.worm).call().await?;
         if allowance != U256::MAX {
             println!("Approving BETH...");
-            let beth_approve_receipt = beth
-                .approve(net.worm, U256::MAX)
-                .send()
+            let tx = gas_opt
+                .apply_to_call(&provider, beth.approve(net.worm, U256::MAX))
                 .await?
-                .get_receipt()
+                .into_transaction_request();
+            let beth_approve_receipt = self
+                .common_opt
+                .send_tracked(params_dir, &provider, tx, "approve")
                 .await?;
             if !beth_approve_receipt.status() {
                 panic!("Failed on BETH approval!");
             }
         }
-        let receipt = worm
-            .participate(amount_per_epoch, U256::from(self.num_epochs))
-            .send()
+        let tx = gas_opt
+            .apply_to_call(
+                &provider,
+                worm.participate(amount_per_epoch, U256::from(self.num_epochs)),
+            )
             .await?
-            .get_receipt()
+            .into_transaction_request();
+        let receipt = self
+            .common_opt
+            .send_tracked(params_dir, &provider, tx, "participate")
             .await?;
         if receipt.status() {
             println!("Success!");
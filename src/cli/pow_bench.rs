@@ -0,0 +1,41 @@
+use crate::pow::{POW_MIN_ZERO_BYTES, default_threads, expected_hashes, hash_rate};
+use std::time::Duration;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+pub struct PowBenchOpt {
+    /// Seconds to run the benchmark for
+    #[structopt(long, default_value = "10")]
+    seconds: u64,
+    /// Threads to benchmark with (default: all cores)
+    #[structopt(long)]
+    threads: Option<usize>,
+    /// Highest byteSecurityRelax to print an estimate for
+    #[structopt(long, default_value = "3")]
+    max_byte_security_relax: u8,
+}
+
+impl PowBenchOpt {
+    pub async fn run(self) -> Result<(), anyhow::Error> {
+        let threads = self.threads.unwrap_or_else(default_threads);
+        println!(
+            "Benchmarking the burn-key search on {} threads for {}s...",
+            threads, self.seconds
+        );
+        let seconds = self.seconds;
+        let rate =
+            tokio::task::spawn_blocking(move || hash_rate(threads, Duration::from_secs(seconds)))
+                .await?;
+        println!("Hash rate: {:.2} MH/s", rate / 1e6);
+        for relax in 0..=self.max_byte_security_relax {
+            let expected = expected_hashes(POW_MIN_ZERO_BYTES + relax as usize);
+            println!(
+                "--byte-security-relax {} => ~{:.0} hashes, expected ~{:.1}s",
+                relax,
+                expected,
+                expected / rate
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,166 +1,421 @@
 use structopt::StructOpt;
 
-use anyhow::{Context, bail};
-use serde_json::Value;
+use anyhow::bail;
+use serde_json::{Value, json};
 
-use super::CommonOpt;
-use crate::cli::utils::check_required_files;
+use super::{BlockOpt, CommonOpt, HookOpt};
+use crate::cli::utils::{
+    BurnStatus, check_burn_commitment, check_required_files, entry_burn_address,
+    entry_byte_security_relax, entry_extra_commitment, entry_intended_amount, entry_mint_after,
+    entry_prover_fee, entry_receiver, entry_receiver_hook, entry_str, find_entry, read_entries,
+    update_entry,
+};
 
 use crate::fp::Fp;
+use crate::utils::{BETH, BurnCommitment, compute_remaining_coin};
 use alloy::{
     hex,
-    primitives::{U256, utils::parse_ether},
+    primitives::{Address, Bytes, U256, utils::parse_ether},
+    providers::Provider,
 };
 use anyhow::anyhow;
 use ff::PrimeField;
-use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
 
 #[derive(StructOpt)]
 pub enum RecoverOpt {
     ById {
         #[structopt(flatten)]
         common_opt: CommonOpt,
+        #[structopt(flatten)]
+        block_opt: BlockOpt,
         #[structopt(long)]
         id: String,
         #[structopt(long)]
         spend: Option<String>,
+        /// Amount the proof commits to, in ETH (default: the recorded amount).
+        /// Anything the burn address holds beyond it stays burnt.
+        #[structopt(long)]
+        intended_amount: Option<String>,
     },
 
     Manual {
         #[structopt(flatten)]
         common_opt: CommonOpt,
+        #[structopt(flatten)]
+        block_opt: BlockOpt,
         #[structopt(long)]
         burn_key: String,
         #[structopt(long)]
         spend: String,
         #[structopt(long)]
         fee: String,
+        #[structopt(long, default_value = "0")]
+        byte_security_relax: u8,
+        /// Amount the proof commits to, in ETH (default: the current balance of
+        /// the burn address). Anything the burn address holds beyond it stays burnt.
+        #[structopt(long)]
+        intended_amount: Option<String>,
+        /// Burn address the ETH was sent to. Recovery stops if the given
+        /// parameters derive a different one.
+        #[structopt(long)]
+        burn_address: Option<Address>,
+        /// Address the burn committed to as the receiver of the minted BETH
+        /// (default: the wallet)
+        #[structopt(long)]
+        receiver: Option<Address>,
+        /// Prover fee the burn committed to, in ETH
+        #[structopt(long, default_value = "0")]
+        prover_fee: String,
+        #[structopt(flatten)]
+        hook_opt: HookOpt,
+    },
+
+    /// Mint every burn in burn.json whose burn address is funded but unminted
+    All {
+        #[structopt(flatten)]
+        common_opt: CommonOpt,
+        #[structopt(flatten)]
+        block_opt: BlockOpt,
     },
 }
+/// Everything needed to re-derive a burn address and mint its burn.
+struct RecoverParams {
+    /// Entry of the burn in burn.json, if it has one
+    id: Option<String>,
+    burn_key: String,
+    spend: U256,
+    fee: U256,
+    byte_security_relax: u8,
+    receiver: Address,
+    receiver_hook: Bytes,
+    prover_fee: U256,
+    intended: Option<U256>,
+    expected_address: Option<Address>,
+    expected_extra_commitment: Option<U256>,
+}
+
+impl RecoverParams {
+    fn from_entry(
+        coin: &Value,
+        wallet: Address,
+        spend: Option<String>,
+        intended_amount: Option<String>,
+    ) -> Result<Self, anyhow::Error> {
+        let burn_key = match coin.get("burnKey") {
+            Some(Value::String(key)) => key.clone(),
+            _ => bail!("burn_key not found in the burn object"),
+        };
+        let fee_str = match coin.get("fee") {
+            Some(Value::String(key)) => key.clone(),
+            _ => bail!("fee not found in the burn object"),
+        };
+        let fee = fee_str.parse::<U256>()?;
+        let stored_spend = match coin.get("spend") {
+            Some(Value::String(key)) => key.clone(),
+            _ => bail!("spend not found in the burn object"),
+        };
+
+        let spend = match spend {
+            Some(s) => parse_ether(&s)?,
+            None => stored_spend.parse::<U256>()?,
+        };
+        let intended = match intended_amount {
+            Some(amount) => Some(parse_ether(&amount)?),
+            None => entry_intended_amount(coin)?,
+        };
+
+        Ok(RecoverParams {
+            id: Some(entry_str(coin, "id")?.to_string()),
+            burn_key,
+            spend,
+            fee,
+            byte_security_relax: entry_byte_security_relax(coin)?,
+            receiver: entry_receiver(coin, wallet)?,
+            receiver_hook: entry_receiver_hook(coin)?,
+            prover_fee: entry_prover_fee(coin)?,
+            intended,
+            expected_address: entry_burn_address(coin)?,
+            expected_extra_commitment: entry_extra_commitment(coin)?,
+        })
+    }
+}
+
 impl RecoverOpt {
     pub async fn run(self, params_dir: &std::path::Path) -> Result<(), anyhow::Error> {
-        let (raw_burn_key, spend, fee, common_opt) = match self {
+        let (params, common_opt, block_opt) = match self {
             RecoverOpt::Manual {
                 burn_key,
                 spend,
                 fee,
+                byte_security_relax,
+                intended_amount,
+                burn_address,
+                receiver,
+                prover_fee,
+                hook_opt,
                 common_opt,
+                block_opt,
             } => {
                 let fee = parse_ether(&fee)?;
+                let prover_fee = parse_ether(&prover_fee)?;
                 let spend = parse_ether(&spend)?;
-                (burn_key, spend, fee, common_opt)
+                let receiver = receiver.unwrap_or_else(|| common_opt.private_key.address());
+                let receiver_hook = hook_opt.receiver_hook(&common_opt, receiver, spend).await?;
+                let intended = intended_amount.as_deref().map(parse_ether).transpose()?;
+                let params = RecoverParams {
+                    id: None,
+                    burn_key,
+                    spend,
+                    fee,
+                    byte_security_relax,
+                    receiver,
+                    receiver_hook,
+                    prover_fee,
+                    intended,
+                    expected_address: burn_address,
+                    expected_extra_commitment: None,
+                };
+                (params, common_opt, block_opt)
             }
 
             RecoverOpt::ById {
                 id,
                 common_opt,
+                block_opt,
                 spend,
+                intended_amount,
             } => {
-                let burn_json_path = "burn.json";
-
-                let burn_path = params_dir.join(burn_json_path);
+                let burn_path = params_dir.join("burn.json");
                 if !burn_path.exists() {
-                    println!("No coins.json found at {}", burn_path.display());
+                    println!("No burn.json found at {}", burn_path.display());
                     return Ok(());
                 }
-                let data = fs::read_to_string(&burn_path)
-                    .with_context(|| format!("failed to read {}", burn_path.display()))?;
-
-                let json: Value = serde_json::from_str(&data)
-                    .with_context(|| format!("failed to parse {} as JSON", burn_path.display()))?;
-
-                let arr = json.as_array().with_context(|| {
-                    format!("expected {} to be a JSON array", burn_path.display())
-                })?;
-
-                let coin = arr
-                    .iter()
-                    .find(|obj| {
-                        obj.get("id").map_or(false, |v| match v {
-                            Value::String(s) => s == &id,
-                            Value::Number(n) => n.to_string() == id,
-                            _ => false,
-                        })
-                    })
-                    .ok_or_else(|| {
-                        anyhow!("no coin with id {} found in {}", id, burn_path.display())
-                    })?;
-                println!("{}", serde_json::to_string_pretty(coin)?);
-                let burn_key = match coin.get("burnKey") {
-                    Some(Value::String(key)) => key.clone(),
-                    _ => bail!("burn_key not found in the burn object"),
-                };
-                let fee_str = match coin.get("fee") {
-                    Some(Value::String(key)) => key.clone(),
-                    _ => bail!("fee not found in the burn object"),
-                };
-                let fee = fee_str.parse::<U256>()?;
-                let stored_spend = match coin.get("spend") {
-                    Some(Value::String(key)) => key.clone(),
-                    _ => bail!("spend not found in the burn object"),
-                };
-
-                let spend = match spend {
-                    Some(s) => parse_ether(&s)?,
-                    None => stored_spend.parse::<U256>()?,
-                };
-
-                (burn_key, spend, fee, common_opt)
+                let coin = find_entry(&burn_path, &id)?;
+                println!("{}", serde_json::to_string_pretty(&coin)?);
+                let params = RecoverParams::from_entry(
+                    &coin,
+                    common_opt.private_key.address(),
+                    spend,
+                    intended_amount,
+                )?;
+                (params, common_opt, block_opt)
             }
-        };
 
-        let burn_key = if raw_burn_key.starts_with("0x") {
-            let hex = raw_burn_key.strip_prefix("0x").unwrap();
-            let bytes = hex::decode(hex)?;
-            Fp::from_be_bytes(&bytes)
-        } else {
-            Fp::from_str_vartime(&raw_burn_key.to_string()).unwrap()
+            RecoverOpt::All {
+                common_opt,
+                block_opt,
+            } => return recover_all(&common_opt, &block_opt, params_dir).await,
         };
 
         check_required_files(params_dir)?;
+        recover_burn(&common_opt, &block_opt, params_dir, params).await?;
+        Ok(())
+    }
+}
 
-        let receiver_hook = Vec::new();
-        let (burn_addr, nullifier_fp, burn_extra_commit) = common_opt
-            .recover_prepare_from_key(burn_key, fee, spend, receiver_hook.clone().into(),)
-            .await?;
+/// What happened to a burn during recovery.
+enum Recovered {
+    Minted,
+    AlreadyMinted,
+}
 
-        println!(
-            "Your burn-key as string: {}",
-            U256::from_le_bytes(burn_key.to_repr().0).to_string()
-        );
-        println!("Your burn-address is: {}", burn_addr);
+/// Re-derives the burn address, checks that it matches the burn and mints the
+/// burn unless its nullifier is already spent.
+async fn recover_burn(
+    common_opt: &CommonOpt,
+    block_opt: &BlockOpt,
+    params_dir: &Path,
+    params: RecoverParams,
+) -> Result<Recovered, anyhow::Error> {
+    let raw_burn_key = params.burn_key;
+    let burn_key = if raw_burn_key.starts_with("0x") {
+        let hex = raw_burn_key.strip_prefix("0x").unwrap();
+        let bytes = hex::decode(hex)?;
+        Fp::from_be_bytes(&bytes)
+    } else {
+        Fp::from_str_vartime(&raw_burn_key.to_string())
+            .ok_or_else(|| anyhow!("invalid burn-key: {}", raw_burn_key))?
+    };
+    let (spend, fee) = (params.spend, params.fee);
 
-        let (remaining_coin_val, remaining_coin_u256) = common_opt
-            .recover_check_balance_and_compute_remaining(burn_addr, burn_key, fee, spend)
-            .await?;
+    let (burn_addr, nullifier_fp, burn_extra_commit) = common_opt
+        .recover_prepare_from_key(
+            burn_key,
+            params.receiver,
+            params.prover_fee,
+            fee,
+            spend,
+            params.receiver_hook.clone(),
+        )
+        .await?;
+    check_burn_commitment(
+        params.expected_address,
+        params.expected_extra_commitment,
+        burn_addr,
+        burn_extra_commit,
+    )?;
 
-        let (json_output, block_number, _out_path) = common_opt
-            .build_and_prove_burn(
+    println!(
+        "Your burn-key as string: {}",
+        U256::from_le_bytes(burn_key.to_repr().0).to_string()
+    );
+    println!("Your burn-address is: {}", burn_addr);
+
+    let nullifier_u256 = U256::from_le_bytes(nullifier_fp.to_repr().0);
+    let rt = common_opt.setup().await?;
+    let beth = BETH::new(rt.network.beth, &rt.provider);
+    if beth.nullifiers(nullifier_u256).call().await? {
+        println!("This burn has already been minted");
+        let amount = match params.intended {
+            Some(amount) => amount,
+            None => rt.provider.get_balance(burn_addr).await?,
+        };
+        let (remaining_coin_val, remaining_coin_u256) =
+            compute_remaining_coin(burn_key, amount, spend)?;
+        common_opt
+            .persist_minted_coin(
                 params_dir,
-                burn_addr,
                 burn_key,
-                spend,
-                burn_extra_commit,
-                "input.json",
-                "witness.wtns",
+                remaining_coin_val,
+                remaining_coin_u256,
             )
             .await?;
+        mark_minted(params_dir, params.id.as_deref())?;
+        return Ok(Recovered::AlreadyMinted);
+    }
 
-        common_opt.persist_burn_data(params_dir, burn_key, remaining_coin_val, None, None, true)?;
+    let (remaining_coin_val, remaining_coin_u256, intended) = common_opt
+        .recover_check_balance_and_compute_remaining(
+            burn_addr,
+            burn_key,
+            fee,
+            spend,
+            params.intended,
+        )
+        .await?;
 
-        let nullifier_u256 = U256::from_le_bytes(nullifier_fp.to_repr().0);
-        common_opt
-            .broadcast_mint(
-                &json_output,
-                block_number,
-                nullifier_u256,
-                remaining_coin_u256,
-                fee,
-                spend,
-                receiver_hook.into(),
-            )
-            .await?;
+    let commitment = BurnCommitment {
+        receiver: params.receiver,
+        receiver_hook: params.receiver_hook,
+        broadcaster_fee: fee,
+        prover_fee: params.prover_fee,
+        spend,
+        prover: Some(common_opt.private_key.address()),
+    };
+    let (json_output, block_number, _out_path) = common_opt
+        .build_and_prove_burn(
+            params_dir,
+            block_opt,
+            burn_key,
+            intended,
+            &commitment,
+            params.byte_security_relax,
+        )
+        .await?;
 
-        Ok(())
+    let minted = common_opt
+        .broadcast_mint(
+            params_dir,
+            &json_output,
+            block_number,
+            nullifier_u256,
+            remaining_coin_u256,
+            &commitment,
+        )
+        .await;
+    // The mint may have landed even though its receipt didn't arrive
+    if let Err(e) = minted {
+        if !beth.nullifiers(nullifier_u256).call().await? {
+            return Err(e);
+        }
+        println!("Minting reported an error but the burn is minted: {:?}", e);
+    }
+    common_opt
+        .persist_minted_coin(
+            params_dir,
+            burn_key,
+            remaining_coin_val,
+            remaining_coin_u256,
+        )
+        .await?;
+    mark_minted(params_dir, params.id.as_deref())?;
+
+    Ok(Recovered::Minted)
+}
+
+/// Walks every burn of the network and mints those whose burn address holds
+/// ETH but whose nullifier is unspent, one after another. Burns scheduled for
+/// later are left alone. A failing burn doesn't stop the others; a summary is
+/// printed at the end.
+async fn recover_all(
+    common_opt: &CommonOpt,
+    block_opt: &BlockOpt,
+    params_dir: &Path,
+) -> Result<(), anyhow::Error> {
+    check_required_files(params_dir)?;
+    let rt = common_opt.setup().await?;
+    let mut summary = Vec::new();
+    for entry in read_entries(params_dir.join("burn.json"))? {
+        if entry.get("network").and_then(Value::as_str) != Some(common_opt.network.as_str()) {
+            continue;
+        }
+        let id = entry_str(&entry, "id")?.to_string();
+        let outcome =
+            match recover_entry(common_opt, block_opt, params_dir, &rt.provider, &entry).await {
+                Ok(outcome) => outcome.to_string(),
+                Err(e) => {
+                    println!("Burn #{} failed: {:?}", id, e);
+                    format!("failed: {}", e)
+                }
+            };
+        summary.push((id, outcome));
+    }
+
+    if summary.is_empty() {
+        println!("No burns found for network {}", common_opt.network);
+    }
+    println!("Summary:");
+    for (id, outcome) in summary {
+        println!("  burn #{}: {}", id, outcome);
+    }
+    Ok(())
+}
+
+async fn recover_entry<P: Provider>(
+    common_opt: &CommonOpt,
+    block_opt: &BlockOpt,
+    params_dir: &Path,
+    provider: &P,
+    entry: &Value,
+) -> Result<&'static str, anyhow::Error> {
+    if entry_mint_after(entry)?.is_some_and(|t| t > SystemTime::now()) {
+        return Ok("scheduled");
+    }
+    let params = RecoverParams::from_entry(entry, common_opt.private_key.address(), None, None)?;
+    // Entries of older versions lack the address; recover_burn checks those
+    if let Some(burn_addr) = params.expected_address
+        && provider.get_balance(burn_addr).await?.is_zero()
+    {
+        return Ok("skipped, the burn address holds no ETH");
+    }
+    println!("Recovering burn #{}...", entry_str(entry, "id")?);
+    Ok(
+        match recover_burn(common_opt, block_opt, params_dir, params).await? {
+            Recovered::Minted => "minted",
+            Recovered::AlreadyMinted => "already minted",
+        },
+    )
+}
+
+fn mark_minted(params_dir: &Path, id: Option<&str>) -> Result<(), anyhow::Error> {
+    match id {
+        Some(id) => update_entry(
+            params_dir.join("burn.json"),
+            id,
+            json!({ "status": BurnStatus::Minted.as_str() }),
+        ),
+        None => Ok(()),
     }
 }
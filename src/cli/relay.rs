@@ -0,0 +1,62 @@
+use super::CommonOpt;
+use crate::decode::report_receipt_logs;
+use crate::server::types::{ApiResponse, RelayResponse};
+use alloy::primitives::TxHash;
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionReceipt;
+use anyhow::{Context, Result, anyhow};
+use reqwest::Url;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Seconds between two receipt polls of a relayed transaction
+const RELAY_POLL_SECS: u64 = 5;
+
+impl CommonOpt {
+    /// Hands a request to the `--relayer` broadcaster at `endpoint` and waits
+    /// for the transaction it sent. The wallet itself never pays gas.
+    pub async fn relay<T: Serialize>(
+        &self,
+        relayer: &Url,
+        endpoint: &str,
+        request: &T,
+    ) -> Result<TransactionReceipt> {
+        let url = format!("{}/{}", relayer.as_str().trim_end_matches('/'), endpoint);
+        println!("Handing the transaction to the relayer at {}...", url);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header("content-type", "application/json")
+            .body(serde_json::to_vec(request)?)
+            .send()
+            .await?;
+        let body = response.bytes().await?;
+        let response: ApiResponse<RelayResponse> = serde_json::from_slice(&body)
+            .with_context(|| format!("unexpected relayer response: {:?}", body))?;
+        let tx_hash: TxHash = response
+            .result
+            .ok_or_else(|| anyhow!("Relayer refused the transaction: {}", response.message))?
+            .tx_hash
+            .parse()?;
+        println!("Relayer sent transaction {}", tx_hash);
+
+        let net = self.overridden_network()?;
+        let provider = ProviderBuilder::new().connect_http(net.rpc.clone());
+        let start = Instant::now();
+        loop {
+            if let Some(receipt) = provider.get_transaction_receipt(tx_hash).await? {
+                report_receipt_logs(&receipt, &net);
+                return Ok(receipt);
+            }
+            if self.receipt_timeout != 0
+                && start.elapsed() > Duration::from_secs(self.receipt_timeout)
+            {
+                return Err(anyhow!(
+                    "Relayed transaction {} was not mined within {} seconds",
+                    tx_hash,
+                    self.receipt_timeout
+                ));
+            }
+            tokio::time::sleep(Duration::from_secs(RELAY_POLL_SECS)).await;
+        }
+    }
+}
@@ -1,21 +1,49 @@
 use super::CommonOpt;
-use crate::cli::utils::check_required_files;
+use super::spend_batch::SpendBatchOpt;
+use crate::cli::utils::{
+    check_required_files, entry_burn_key, entry_str, find_entry, read_entries,
+};
 use crate::fp::Fp;
+use crate::utils::{BETH, compute_previous_coin};
 
-use alloy::primitives::{Address, U256, utils::parse_ether};
+use alloy::primitives::utils::format_ether;
+use alloy::primitives::{Address, B256, U256, utils::parse_ether};
+use alloy::providers::Provider;
 use anyhow::{Context, Result, anyhow, bail};
 use serde_json::Value;
-use std::{fs, str::FromStr};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
 use structopt::StructOpt;
 
-use ff::PrimeField;
+#[derive(StructOpt)]
+pub enum SpendCommand {
+    /// Spend BETH of a single coin
+    Run(SpendOpt),
+    /// Pay every row of a CSV (receiver,amount[,fee]) through chained spends
+    Batch(SpendBatchOpt),
+}
+
+impl SpendCommand {
+    pub async fn run(self, params_dir: &Path) -> Result<()> {
+        match self {
+            SpendCommand::Run(opt) => opt.run(params_dir).await,
+            SpendCommand::Batch(opt) => opt.run(params_dir).await,
+        }
+    }
+}
 
 #[derive(StructOpt)]
 pub struct SpendOpt {
     #[structopt(flatten)]
     common_opt: CommonOpt,
-    #[structopt(long, default_value = "anvil")]
-    id: String,
+    /// Coin to spend (default: picked among the live coins by --coin-selection)
+    #[structopt(long)]
+    id: Option<String>,
+    /// How to pick the coin: smallest (smallest coin that covers the spend),
+    /// oldest, or privacy (a coin spent without change if any, else a random one)
+    #[structopt(long, default_value = "smallest")]
+    coin_selection: CoinSelection,
     #[structopt(long)]
     amount: String,
     #[structopt(long)]
@@ -24,6 +52,166 @@ pub struct SpendOpt {
     receiver: Address,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoinSelection {
+    Smallest,
+    Oldest,
+    Privacy,
+}
+
+impl FromStr for CoinSelection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "smallest" => Ok(CoinSelection::Smallest),
+            "oldest" => Ok(CoinSelection::Oldest),
+            "privacy" => Ok(CoinSelection::Privacy),
+            _ => bail!(
+                "unknown coin selection: {} (smallest, oldest or privacy)",
+                s
+            ),
+        }
+    }
+}
+
+/// An entry of coins.json. It may have been spent since; `is_live` tells.
+#[derive(Debug, Clone)]
+pub struct Coin {
+    pub id: String,
+    pub burn_key: Fp,
+    pub amount: U256,
+}
+
+impl Coin {
+    fn from_entry(entry: &Value) -> Result<Self> {
+        Ok(Coin {
+            id: entry_str(entry, "id")?.to_string(),
+            burn_key: entry_burn_key(entry)?,
+            amount: entry_str(entry, "amount")?.parse()?,
+        })
+    }
+
+    /// Whether the commitment of the coin is still unspent on-chain.
+    pub async fn is_live<P: Provider>(&self, provider: &P, beth: Address) -> Result<bool> {
+        let (_, commitment) = compute_previous_coin(self.burn_key, self.amount)?;
+        Ok(!BETH::new(beth, provider)
+            .coins(commitment)
+            .call()
+            .await?
+            .is_zero())
+    }
+}
+
+/// Looks up a coin of coins.json by id, spent or not.
+pub fn find_coin(params_dir: &Path, id: &str) -> Result<Coin> {
+    let coins_path = params_dir.join("coins.json");
+    Coin::from_entry(&find_entry(&coins_path, id)?)
+}
+
+/// The coins of coins.json on the selected network that can still be spent,
+/// oldest first.
+pub async fn live_coins(common_opt: &CommonOpt, params_dir: &Path) -> Result<Vec<Coin>> {
+    let rt = common_opt.setup().await?;
+    let mut coins = Vec::new();
+    for entry in read_entries(params_dir.join("coins.json"))? {
+        if entry.get("network").and_then(Value::as_str) != Some(common_opt.network.as_str()) {
+            continue;
+        }
+        let coin = Coin::from_entry(&entry)?;
+        if !coin.amount.is_zero() && coin.is_live(&rt.provider, rt.network.beth).await? {
+            coins.push(coin);
+        }
+    }
+    Ok(coins)
+}
+
+/// Picks the coin to take `needed` from, if any single coin covers it.
+pub fn select_coin(coins: &[Coin], needed: U256, policy: CoinSelection) -> Option<Coin> {
+    let candidates = coins
+        .iter()
+        .filter(|c| c.amount >= needed)
+        .collect::<Vec<_>>();
+    let coin = match policy {
+        CoinSelection::Smallest => candidates.into_iter().min_by_key(|c| c.amount),
+        CoinSelection::Oldest => candidates.into_iter().next(),
+        // Spending a coin entirely leaves no remaining coin to link to the
+        // spend; otherwise avoid a predictable choice
+        CoinSelection::Privacy => match candidates.iter().find(|c| c.amount == needed) {
+            Some(exact) => Some(*exact),
+            None if candidates.is_empty() => None,
+            None => {
+                let random = u64::from_be_bytes(B256::random().0[..8].try_into().unwrap());
+                Some(candidates[random as usize % candidates.len()])
+            }
+        },
+    };
+    coin.cloned()
+}
+
+/// Finds the coin given by `--id`, or picks one covering `needed`. When no
+/// single coin covers it, offers to spend the largest coin as far as it goes
+/// and returns the reduced spend amount.
+pub async fn choose_coin(
+    common_opt: &CommonOpt,
+    params_dir: &Path,
+    id: Option<&str>,
+    policy: CoinSelection,
+    out_amount: U256,
+    fee: U256,
+) -> Result<(Coin, U256)> {
+    if let Some(id) = id {
+        let coin = find_coin(params_dir, id)?;
+        let rt = common_opt.setup().await?;
+        if !coin.is_live(&rt.provider, rt.network.beth).await? {
+            bail!("Coin #{} has already been spent or was never minted", id);
+        }
+        return Ok((coin, out_amount));
+    }
+
+    let coins = live_coins(common_opt, params_dir).await?;
+    if let Some(coin) = select_coin(&coins, out_amount + fee, policy) {
+        println!(
+            "Spending coin #{} ({} BETH)",
+            coin.id,
+            format_ether(coin.amount)
+        );
+        return Ok((coin, out_amount));
+    }
+
+    let largest = coins
+        .iter()
+        .max_by_key(|c| c.amount)
+        .filter(|c| c.amount > fee)
+        .ok_or_else(|| {
+            anyhow!(
+                "No live coin on network {} covers the fee",
+                common_opt.network
+            )
+        })?;
+    println!(
+        "No single coin covers {} BETH plus a {} BETH fee. Live coins:",
+        format_ether(out_amount),
+        format_ether(fee)
+    );
+    for coin in &coins {
+        println!("  #{}: {} BETH", coin.id, format_ether(coin.amount));
+    }
+    let reduced = largest.amount - fee;
+    print!(
+        "Spend {} BETH from coin #{} instead? [y/N] ",
+        format_ether(reduced),
+        largest.id
+    );
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        bail!("Spend aborted");
+    }
+    Ok((largest.clone(), reduced))
+}
+
 impl SpendOpt {
     pub async fn run(self, params_dir: &std::path::Path) -> Result<(), anyhow::Error> {
         println!("starting spend operation...");
@@ -34,51 +222,34 @@ impl SpendOpt {
             println!("No coins.json found at {}", coins_path.display());
             return Ok(());
         }
-        let data = fs::read_to_string(&coins_path)
-            .with_context(|| format!("failed to read {}", coins_path.display()))?;
-        let json: Value = serde_json::from_str(&data)
-            .with_context(|| format!("failed to parse {} as JSON", coins_path.display()))?;
-        let arr = json
-            .as_array()
-            .with_context(|| format!("expected {} to be a JSON array", coins_path.display()))?;
-
-        let coin = arr
-            .iter()
-            .find(|obj| {
-                obj.get("id").map_or(false, |v| match v {
-                    Value::String(s) => s == &self.id,
-                    Value::Number(n) => n.to_string() == self.id,
-                    _ => false,
-                })
-            })
-            .ok_or_else(|| {
-                anyhow!(
-                    "no coin with id {} found in {}",
-                    self.id,
-                    coins_path.display()
-                )
-            })?;
-        println!("{}", serde_json::to_string_pretty(coin)?);
-
-        let burn_key_str = match coin.get("burnKey") {
-            Some(Value::String(key)) => key.clone(),
-            _ => bail!("burn_key not found in the coin object"),
-        };
-        let original_amount_str = match coin.get("amount") {
-            Some(Value::String(amount)) => amount.clone(),
-            _ => bail!("amount not found in the coin object"),
-        };
-
-        let burn_key_fp = Fp::from_str_vartime(&burn_key_str.to_string()).unwrap();
-        let original_amount_u256 =
-            U256::from_str(&original_amount_str).expect("Invalid U256 string");
 
         let fee = parse_ether(&self.fee)?;
-        let out_amount = parse_ether(&self.amount)?;
+        let (coin, out_amount) = choose_coin(
+            &self.common_opt,
+            params_dir,
+            self.id.as_deref(),
+            self.coin_selection,
+            parse_ether(&self.amount)?,
+            fee,
+        )
+        .await
+        .with_context(|| format!("failed to pick a coin from {}", coins_path.display()))?;
+
+        let burn_key_fp = coin.burn_key;
+        let original_amount_u256 = coin.amount;
 
         let (previous_coin_u256, remaining_coin_val_fp, remaining_coin_u256) = self
             .common_opt
             .spend_prepare_from_coin(burn_key_fp, original_amount_u256, out_amount, fee)?;
+        // The coin may have been spent since it was picked, e.g. while the
+        // reduced amount was being confirmed
+        let rt = self.common_opt.setup().await?;
+        if !coin.is_live(&rt.provider, rt.network.beth).await? {
+            bail!(
+                "Coin #{} has already been spent or was never minted",
+                coin.id
+            );
+        }
 
         let proof = self
             .common_opt
@@ -94,17 +265,12 @@ impl SpendOpt {
             )
             .await?;
 
-        self.common_opt.persist_burn_data(
-            params_dir,
-            burn_key_fp,
-            remaining_coin_val_fp,
-            None,
-            None,
-            true,
-        )?;
+        self.common_opt
+            .persist_coin(params_dir, burn_key_fp, remaining_coin_val_fp)?;
 
         self.common_opt
             .broadcast_spend(
+                params_dir,
                 &proof,
                 previous_coin_u256,
                 out_amount,
@@ -117,3 +283,91 @@ impl SpendOpt {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coins(amounts: &[u64]) -> Vec<Coin> {
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(i, amount)| Coin {
+                id: (i + 1).to_string(),
+                burn_key: Fp::from(i as u64 + 1),
+                amount: U256::from(*amount),
+            })
+            .collect()
+    }
+
+    fn selected(coins: &[Coin], needed: u64, policy: CoinSelection) -> Option<String> {
+        select_coin(coins, U256::from(needed), policy).map(|c| c.id)
+    }
+
+    #[test]
+    fn coin_selection_parses_policies() {
+        assert_eq!(
+            "smallest".parse::<CoinSelection>().unwrap(),
+            CoinSelection::Smallest
+        );
+        assert_eq!(
+            "oldest".parse::<CoinSelection>().unwrap(),
+            CoinSelection::Oldest
+        );
+        assert_eq!(
+            "privacy".parse::<CoinSelection>().unwrap(),
+            CoinSelection::Privacy
+        );
+        assert!("largest".parse::<CoinSelection>().is_err());
+    }
+
+    #[test]
+    fn select_coin_smallest_takes_the_smallest_covering_coin() {
+        let coins = coins(&[50, 10, 30, 20]);
+        assert_eq!(
+            selected(&coins, 15, CoinSelection::Smallest).as_deref(),
+            Some("4")
+        );
+    }
+
+    #[test]
+    fn select_coin_oldest_takes_the_first_covering_coin() {
+        let coins = coins(&[10, 50, 30]);
+        assert_eq!(
+            selected(&coins, 20, CoinSelection::Oldest).as_deref(),
+            Some("2")
+        );
+    }
+
+    #[test]
+    fn select_coin_privacy_prefers_an_exact_coin() {
+        let coins = coins(&[50, 20, 30]);
+        for _ in 0..20 {
+            assert_eq!(
+                selected(&coins, 20, CoinSelection::Privacy).as_deref(),
+                Some("2")
+            );
+        }
+    }
+
+    #[test]
+    fn select_coin_privacy_picks_among_covering_coins() {
+        let coins = coins(&[50, 10, 30]);
+        for _ in 0..20 {
+            let id = selected(&coins, 25, CoinSelection::Privacy).unwrap();
+            assert!(id == "1" || id == "3");
+        }
+    }
+
+    #[test]
+    fn select_coin_finds_nothing_when_no_coin_covers() {
+        let coins = coins(&[10, 20]);
+        for policy in [
+            CoinSelection::Smallest,
+            CoinSelection::Oldest,
+            CoinSelection::Privacy,
+        ] {
+            assert_eq!(selected(&coins, 21, policy), None);
+        }
+    }
+}
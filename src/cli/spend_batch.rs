@@ -0,0 +1,553 @@
+use super::CommonOpt;
+use super::spend::{Coin, CoinSelection, find_coin, live_coins, select_coin};
+use super::tx::transactions_path;
+use crate::cli::utils::{check_required_files, entry_str, read_entries};
+use crate::utils::{BETH, RapidsnarkOutput};
+
+use alloy::primitives::utils::{format_ether, parse_ether};
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::Provider;
+use alloy::sol_types::SolCall;
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+pub struct SpendBatchOpt {
+    #[structopt(flatten)]
+    common_opt: CommonOpt,
+    /// CSV with one payout per line: receiver,amount[,fee] (amounts in BETH)
+    #[structopt(parse(from_os_str))]
+    csv: PathBuf,
+    /// Fee of the rows that don't set one, in BETH
+    #[structopt(long, default_value = "0")]
+    fee: String,
+    /// Coin to pay from (default: picked by --coin-selection)
+    #[structopt(long)]
+    id: Option<String>,
+    /// How to pick the coin: smallest, oldest or privacy
+    #[structopt(long, default_value = "smallest")]
+    coin_selection: CoinSelection,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum RowStatus {
+    Pending,
+    Proved,
+    Spent,
+}
+
+/// Progress of a batch, stored under `batches/` so that running the same
+/// command again continues where it stopped.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchState {
+    csv: String,
+    network: String,
+    rows: Vec<BatchRow>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BatchRow {
+    receiver: Address,
+    amount: String,
+    fee: String,
+    status: RowStatus,
+    /// Coin the payout is taken from
+    coin_id: Option<String>,
+    /// Coin left after the payout, spent by the next row
+    remaining_coin_id: Option<String>,
+    proof_path: Option<String>,
+    tx_hash: Option<String>,
+}
+
+impl BatchRow {
+    fn amounts(&self) -> Result<(U256, U256)> {
+        Ok((self.amount.parse()?, self.fee.parse()?))
+    }
+}
+
+impl SpendBatchOpt {
+    /// Generates the proofs of all payouts back-to-back, each one spending the
+    /// remaining coin of the previous, then broadcasts them in order.
+    pub async fn run(self, params_dir: &Path) -> Result<()> {
+        check_required_files(params_dir)?;
+        let payouts = read_payouts(&self.csv, parse_ether(&self.fee)?)?;
+        if payouts.is_empty() {
+            bail!("{} has no payouts", self.csv.display());
+        }
+        let state_path = self.state_path(params_dir)?;
+        let mut state = match fs::read(&state_path) {
+            Ok(data) => {
+                let state: BatchState = serde_json::from_slice(&data)
+                    .with_context(|| format!("failed to parse {}", state_path.display()))?;
+                let unchanged = state.rows.len() == payouts.len()
+                    && state.rows.iter().zip(&payouts).all(|(row, payout)| {
+                        (row.receiver, row.amount.as_str(), row.fee.as_str())
+                            == (payout.receiver, payout.amount.as_str(), payout.fee.as_str())
+                    });
+                if !unchanged || state.network != self.common_opt.network {
+                    bail!(
+                        "{} changed since its batch was started; finish or remove {} first",
+                        self.csv.display(),
+                        state_path.display()
+                    );
+                }
+                let spent = state
+                    .rows
+                    .iter()
+                    .filter(|r| r.status == RowStatus::Spent)
+                    .count();
+                println!(
+                    "Resuming batch: {} of {} payouts already spent",
+                    spent,
+                    state.rows.len()
+                );
+                state
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => BatchState {
+                csv: self.csv.display().to_string(),
+                network: self.common_opt.network.clone(),
+                rows: payouts,
+            },
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to read {}", state_path.display()));
+            }
+        };
+
+        self.prove_rows(params_dir, &state_path, &mut state).await?;
+        self.broadcast_rows(params_dir, &state_path, &mut state)
+            .await?;
+
+        let report_path = self.csv.with_extension("receipts.csv");
+        write_report(&report_path, &state)?;
+        println!(
+            "All {} payouts are spent. Receipts written to {}",
+            state.rows.len(),
+            report_path.display()
+        );
+        Ok(())
+    }
+
+    fn state_path(&self, params_dir: &Path) -> Result<PathBuf> {
+        let stem = self
+            .csv
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| anyhow!("invalid CSV path {}", self.csv.display()))?;
+        Ok(params_dir.join("batches").join(format!("{}.json", stem)))
+    }
+
+    /// The coin paying the first row: `--id`, or a live coin covering the
+    /// whole batch.
+    async fn first_coin(&self, params_dir: &Path, rows: &[BatchRow]) -> Result<Coin> {
+        let mut total = U256::ZERO;
+        for row in rows {
+            let (amount, fee) = row.amounts()?;
+            total += amount + fee;
+        }
+        let coin = match &self.id {
+            Some(id) => {
+                let coin = find_coin(params_dir, id)?;
+                let rt = self.common_opt.setup().await?;
+                if !coin.is_live(&rt.provider, rt.network.beth).await? {
+                    bail!("Coin #{} has already been spent or was never minted", id);
+                }
+                coin
+            }
+            None => {
+                let coins = live_coins(&self.common_opt, params_dir).await?;
+                select_coin(&coins, total, self.coin_selection).ok_or_else(|| {
+                    anyhow!(
+                        "No single live coin covers the {} BETH of the batch",
+                        format_ether(total)
+                    )
+                })?
+            }
+        };
+        if coin.amount < total {
+            bail!(
+                "Coin #{} holds {} BETH, but the batch needs {} BETH",
+                coin.id,
+                format_ether(coin.amount),
+                format_ether(total)
+            );
+        }
+        println!(
+            "Paying the batch from coin #{} ({} BETH)",
+            coin.id,
+            format_ether(coin.amount)
+        );
+        Ok(coin)
+    }
+
+    /// Chained coins only reach BETH once the payouts before them are sent, so
+    /// check the coin the unsent part of the chain starts from instead.
+    async fn check_chain_start(&self, params_dir: &Path, rows: &[BatchRow]) -> Result<()> {
+        let id = match rows.iter().find(|r| r.status != RowStatus::Spent) {
+            Some(row) => row.coin_id.as_deref(),
+            None => rows.last().and_then(|r| r.remaining_coin_id.as_deref()),
+        }
+        .context("the batch has no coin to continue from")?;
+        let coin = find_coin(params_dir, id)?;
+        let rt = self.common_opt.setup().await?;
+        if !coin.is_live(&rt.provider, rt.network.beth).await? {
+            bail!(
+                "Coin #{} has already been spent or was never minted",
+                coin.id
+            );
+        }
+        Ok(())
+    }
+
+    async fn prove_rows(
+        &self,
+        params_dir: &Path,
+        state_path: &Path,
+        state: &mut BatchState,
+    ) -> Result<()> {
+        let mut chain_checked = false;
+        for i in 0..state.rows.len() {
+            if state.rows[i].status != RowStatus::Pending {
+                continue;
+            }
+            let coin = match i {
+                0 => self.first_coin(params_dir, &state.rows).await?,
+                _ => {
+                    if !chain_checked {
+                        self.check_chain_start(params_dir, &state.rows[..i]).await?;
+                    }
+                    let id = state.rows[i - 1]
+                        .remaining_coin_id
+                        .clone()
+                        .context("the previous payout has no remaining coin")?;
+                    find_coin(params_dir, &id)?
+                }
+            };
+            chain_checked = true;
+            let row = &state.rows[i];
+            let (amount, fee) = row.amounts()?;
+            println!(
+                "Proving payout {}/{}: {} BETH to {}",
+                i + 1,
+                state.rows.len(),
+                format_ether(amount),
+                row.receiver
+            );
+            let (_, remaining_coin_val_fp, _) =
+                self.common_opt
+                    .spend_prepare_from_coin(coin.burn_key, coin.amount, amount, fee)?;
+            let proof = self
+                .common_opt
+                .build_and_prove_spend(
+                    params_dir,
+                    coin.burn_key,
+                    coin.amount,
+                    amount,
+                    fee,
+                    row.receiver,
+                    "spend_input.json",
+                    "spend_witness.wtns",
+                )
+                .await?;
+
+            let proof_path = state_path.with_file_name(format!(
+                "{}_{}.proof.json",
+                state_path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("batch"),
+                i + 1
+            ));
+            fs::write(&proof_path, serde_json::to_string_pretty(&proof)?)?;
+            let remaining_coin_id =
+                self.common_opt
+                    .persist_coin(params_dir, coin.burn_key, remaining_coin_val_fp)?;
+
+            let row = &mut state.rows[i];
+            row.coin_id = Some(coin.id);
+            row.remaining_coin_id = Some(remaining_coin_id);
+            row.proof_path = Some(proof_path.display().to_string());
+            row.status = RowStatus::Proved;
+            save_state(state_path, state)?;
+        }
+        Ok(())
+    }
+
+    async fn broadcast_rows(
+        &self,
+        params_dir: &Path,
+        state_path: &Path,
+        state: &mut BatchState,
+    ) -> Result<()> {
+        let rt = self.common_opt.setup().await?;
+        for i in 0..state.rows.len() {
+            let row = state.rows[i].clone();
+            if row.status != RowStatus::Proved {
+                continue;
+            }
+            let coin = find_coin(
+                params_dir,
+                row.coin_id.as_deref().context("coinId not found")?,
+            )?;
+            let remaining = find_coin(
+                params_dir,
+                row.remaining_coin_id
+                    .as_deref()
+                    .context("remainingCoinId not found")?,
+            )?;
+            let (amount, fee) = row.amounts()?;
+            let (previous_coin_u256, _, remaining_coin_u256) = self
+                .common_opt
+                .spend_prepare_from_coin(coin.burn_key, coin.amount, amount, fee)?;
+            if !coin.is_live(&rt.provider, rt.network.beth).await? {
+                // The previous run may have died right after broadcasting
+                if let Some(tx_hash) = find_spend_tx(
+                    params_dir,
+                    &rt.provider,
+                    &self.common_opt.network,
+                    previous_coin_u256,
+                )
+                .await?
+                {
+                    println!("Payout {} has already been spent in {}", i + 1, tx_hash);
+                    state.rows[i].tx_hash = Some(tx_hash);
+                    state.rows[i].status = RowStatus::Spent;
+                    save_state(state_path, state)?;
+                    continue;
+                }
+                // Relayed spends aren't in transactions.json, but leave a
+                // remaining coin unless they paid out the whole coin
+                if remaining.is_live(&rt.provider, rt.network.beth).await? {
+                    println!("Payout {} has already been spent", i + 1);
+                    state.rows[i].status = RowStatus::Spent;
+                    save_state(state_path, state)?;
+                    continue;
+                }
+                bail!(
+                    "Coin #{} of payout {} is no longer spendable; was it spent elsewhere?",
+                    coin.id,
+                    i + 1
+                );
+            }
+
+            let proof_path = row.proof_path.as_deref().context("proofPath not found")?;
+            let proof: RapidsnarkOutput = serde_json::from_slice(
+                &fs::read(proof_path).with_context(|| format!("failed to read {}", proof_path))?,
+            )?;
+            println!(
+                "Sending payout {}/{}: {} BETH to {}",
+                i + 1,
+                state.rows.len(),
+                format_ether(amount),
+                row.receiver
+            );
+            let tx_hash = self
+                .common_opt
+                .broadcast_spend(
+                    params_dir,
+                    &proof,
+                    previous_coin_u256,
+                    amount,
+                    remaining_coin_u256,
+                    fee,
+                    row.receiver,
+                )
+                .await
+                .with_context(|| {
+                    format!(
+                        "payout {} failed; run the same command again to continue",
+                        i + 1
+                    )
+                })?;
+            state.rows[i].tx_hash = Some(tx_hash);
+            state.rows[i].status = RowStatus::Spent;
+            save_state(state_path, state)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hash of a successful spendCoin of `coin` sent from this wallet, found in
+/// transactions.json. Catches payouts sent by a run that died before their
+/// receipt arrived.
+async fn find_spend_tx<P: Provider>(
+    params_dir: &Path,
+    provider: &P,
+    network: &str,
+    coin: U256,
+) -> Result<Option<String>> {
+    for entry in read_entries(transactions_path(params_dir))? {
+        if entry.get("label").and_then(Value::as_str) != Some("spend")
+            || entry.get("network").and_then(Value::as_str) != Some(network)
+        {
+            continue;
+        }
+        let input: Bytes = entry_str(&entry, "input")?.parse()?;
+        match BETH::spendCoinCall::abi_decode(&input) {
+            Ok(call) if call._coin == coin => {}
+            _ => continue,
+        }
+        let hash = entry_str(&entry, "hash")?;
+        if let Some(receipt) = provider.get_transaction_receipt(hash.parse()?).await?
+            && receipt.status()
+        {
+            return Ok(Some(hash.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Parses `receiver,amount[,fee]` lines. Blank lines, `#` comments and a
+/// header line, the first other line unless its receiver is hex, are skipped.
+fn read_payouts(path: &Path, default_fee: U256) -> Result<Vec<BatchRow>> {
+    let data =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut rows = Vec::new();
+    let mut first = true;
+    for (n, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        let is_hex = fields[0]
+            .trim_start_matches("0x")
+            .chars()
+            .all(|c| c.is_ascii_hexdigit());
+        let header = first && !is_hex;
+        first = false;
+        let receiver = match fields[0].parse::<Address>() {
+            Ok(receiver) => receiver,
+            Err(_) if header => continue,
+            Err(e) => bail!("line {}: invalid receiver {}: {}", n + 1, fields[0], e),
+        };
+        let amount = match fields.get(1) {
+            Some(amount) => {
+                parse_ether(amount).with_context(|| format!("line {}: invalid amount", n + 1))?
+            }
+            None => bail!("line {}: missing amount", n + 1),
+        };
+        let fee = match fields.get(2).filter(|f| !f.is_empty()) {
+            Some(fee) => {
+                parse_ether(fee).with_context(|| format!("line {}: invalid fee", n + 1))?
+            }
+            None => default_fee,
+        };
+        rows.push(BatchRow {
+            receiver,
+            amount: amount.to_string(),
+            fee: fee.to_string(),
+            status: RowStatus::Pending,
+            coin_id: None,
+            remaining_coin_id: None,
+            proof_path: None,
+            tx_hash: None,
+        });
+    }
+    Ok(rows)
+}
+
+fn save_state(path: &Path, state: &BatchState) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(state)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn write_report(path: &Path, state: &BatchState) -> Result<()> {
+    let mut report = String::from("receiver,amount,fee,tx_hash\n");
+    for row in &state.rows {
+        let (amount, fee) = row.amounts()?;
+        report += &format!(
+            "{},{},{},{}\n",
+            row.receiver,
+            format_ether(amount),
+            format_ether(fee),
+            row.tx_hash.as_deref().unwrap_or("")
+        );
+    }
+    fs::write(path, report).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payouts(name: &str, csv: &str) -> Result<Vec<BatchRow>> {
+        let path =
+            std::env::temp_dir().join(format!("worm-miner-{}-{}.csv", std::process::id(), name));
+        fs::write(&path, csv).unwrap();
+        let rows = read_payouts(&path, parse_ether("0.001").unwrap());
+        fs::remove_file(&path).unwrap();
+        rows
+    }
+
+    #[test]
+    fn read_payouts_skips_header_comments_and_blank_lines() {
+        let rows = payouts(
+            "header",
+            "# payroll\n\nreceiver,amount,fee\n\
+             0x00000000000000000000000000000000000000aa,1.5\n\
+             0x00000000000000000000000000000000000000bb, 0.25 , 0.01\n",
+        )
+        .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].receiver, Address::with_last_byte(0xaa));
+        assert_eq!(
+            rows[0].amounts().unwrap(),
+            (parse_ether("1.5").unwrap(), parse_ether("0.001").unwrap())
+        );
+        assert_eq!(rows[1].receiver, Address::with_last_byte(0xbb));
+        assert_eq!(
+            rows[1].amounts().unwrap(),
+            (parse_ether("0.25").unwrap(), parse_ether("0.01").unwrap())
+        );
+        assert!(rows.iter().all(|r| r.status == RowStatus::Pending));
+    }
+
+    #[test]
+    fn read_payouts_rejects_a_bad_receiver_after_the_first_row() {
+        let err = payouts(
+            "receiver",
+            "0x00000000000000000000000000000000000000aa,1\nnot-an-address,1\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn read_payouts_rejects_a_bad_first_row_after_the_header() {
+        let err = payouts(
+            "first",
+            "receiver,amount\n0x00000000000000000000000000000000000000a,1\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn read_payouts_rejects_a_bad_first_row_without_header() {
+        let err = payouts("typo", "0x00000000000000000000000000000000000000a,1\n").unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+    }
+
+    #[test]
+    fn read_payouts_rejects_missing_or_invalid_amounts() {
+        assert!(payouts("missing", "0x00000000000000000000000000000000000000aa\n").is_err());
+        assert!(
+            payouts(
+                "invalid",
+                "0x00000000000000000000000000000000000000aa,lots\n"
+            )
+            .is_err()
+        );
+    }
+}
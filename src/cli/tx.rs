@@ -0,0 +1,326 @@
+use super::CommonOpt;
+use crate::cli::utils::{
+    append_new_entry, entry_str, init_coins_file, next_id, read_entries, update_entry,
+};
+use crate::decode::report_receipt_logs;
+use crate::gas::GasPrice;
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::{TransactionReceipt, TransactionRequest};
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::time::Duration;
+use structopt::StructOpt;
+
+/// Replacements must pay at least 10% more than the stuck transaction; bump
+/// by 12.5% so every client accepts them.
+const FEE_BUMP_NUMERATOR: u128 = 1125;
+const FEE_BUMP_DENOMINATOR: u128 = 1000;
+
+#[derive(StructOpt)]
+pub enum TxCommand {
+    /// List the transactions sent by worm-miner
+    Ls(TxLsOpt),
+    /// Re-send a pending transaction with the same nonce and higher fees
+    Speedup(TxHashOpt),
+    /// Replace a pending transaction with an empty self-transfer
+    Cancel(TxHashOpt),
+}
+
+#[derive(StructOpt)]
+pub struct TxLsOpt {
+    /// The network to filter by (default: anvil)
+    #[structopt(long, default_value = "anvil")]
+    network: String,
+    /// Only show transactions that are still pending
+    #[structopt(long)]
+    pending: bool,
+}
+
+#[derive(StructOpt)]
+pub struct TxHashOpt {
+    #[structopt(flatten)]
+    common_opt: CommonOpt,
+    /// Hash of the transaction to replace
+    hash: String,
+}
+
+pub(super) fn transactions_path(params_dir: &Path) -> std::path::PathBuf {
+    params_dir.join("transactions.json")
+}
+
+fn find_tx(params_dir: &Path, hash: &str) -> Result<Value> {
+    let path = transactions_path(params_dir);
+    read_entries(&path)?
+        .into_iter()
+        .find(|entry| {
+            entry
+                .get("hash")
+                .and_then(Value::as_str)
+                .is_some_and(|h| h.eq_ignore_ascii_case(hash))
+        })
+        .ok_or_else(|| anyhow!("no transaction {} found in {}", hash, path.display()))
+}
+
+fn bump(fee: u128) -> u128 {
+    (fee * FEE_BUMP_NUMERATOR).div_ceil(FEE_BUMP_DENOMINATOR)
+}
+
+fn entry_u128(entry: &Value, field: &str) -> Result<Option<u128>> {
+    match entry.get(field).and_then(Value::as_str) {
+        Some(v) => Ok(Some(v.parse()?)),
+        None => Ok(None),
+    }
+}
+
+impl TxCommand {
+    pub async fn run(self, params_dir: &Path) -> Result<()> {
+        match self {
+            TxCommand::Ls(opt) => {
+                let entries = read_entries(transactions_path(params_dir))?;
+                let matches = entries
+                    .into_iter()
+                    .filter(|entry| {
+                        entry.get("network").and_then(Value::as_str) == Some(opt.network.as_str())
+                    })
+                    .filter(|entry| {
+                        !opt.pending
+                            || entry.get("status").and_then(Value::as_str) == Some("pending")
+                    })
+                    .collect::<Vec<_>>();
+                if matches.is_empty() {
+                    println!("No transactions found for network: \"{}\"", opt.network);
+                }
+                for entry in matches {
+                    println!(
+                        "{} nonce={} {} [{}]",
+                        entry_str(&entry, "hash")?,
+                        entry.get("nonce").unwrap_or(&Value::Null),
+                        entry_str(&entry, "label").unwrap_or("-"),
+                        entry_str(&entry, "status")?
+                    );
+                }
+                Ok(())
+            }
+            TxCommand::Speedup(opt) => {
+                opt.common_opt
+                    .replace_tx(params_dir, &opt.hash, false)
+                    .await
+            }
+            TxCommand::Cancel(opt) => opt.common_opt.replace_tx(params_dir, &opt.hash, true).await,
+        }
+    }
+}
+
+impl CommonOpt {
+    fn receipt_timeout(&self) -> Option<Duration> {
+        match self.receipt_timeout {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// Sends a transaction whose gas has already been set, after recording its
+    /// hash and nonce in transactions.json, then waits for its receipt for at
+    /// most `--receipt-timeout` seconds.
+    pub async fn send_tracked<P: Provider>(
+        &self,
+        params_dir: &Path,
+        provider: &P,
+        tx: TransactionRequest,
+        label: &str,
+    ) -> Result<TransactionReceipt> {
+        let from = self.private_key.address();
+        let tx = match tx.nonce {
+            Some(_) => tx,
+            None => tx.with_nonce(provider.get_transaction_count(from).pending().await?),
+        };
+        let tx = match tx.chain_id {
+            Some(_) => tx,
+            None => tx.with_chain_id(provider.get_chain_id().await?),
+        };
+        let tx = tx.with_from(from);
+        let pending = provider.send_transaction(tx.clone()).await?;
+        let hash = pending.tx_hash().to_string();
+        println!("Sent {} transaction {}", label, hash);
+        self.record_tx(params_dir, &hash, &tx, label, None)?;
+        self.wait_tracked(params_dir, pending, &hash).await
+    }
+
+    async fn wait_tracked(
+        &self,
+        params_dir: &Path,
+        pending: alloy::providers::PendingTransactionBuilder<alloy::network::Ethereum>,
+        hash: &str,
+    ) -> Result<TransactionReceipt> {
+        let receipt = pending
+            .with_timeout(self.receipt_timeout())
+            .get_receipt()
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "Transaction {} was not mined ({}). Replace it with `worm-miner tx speedup {}` or `worm-miner tx cancel {}`",
+                    hash,
+                    e,
+                    hash,
+                    hash
+                )
+            })?;
+        let status = if receipt.status() { "mined" } else { "failed" };
+        update_tx_status(params_dir, hash, status)?;
+        report_receipt_logs(&receipt, &self.overridden_network()?);
+        Ok(receipt)
+    }
+
+    fn record_tx(
+        &self,
+        params_dir: &Path,
+        hash: &str,
+        tx: &TransactionRequest,
+        label: &str,
+        replaces: Option<&str>,
+    ) -> Result<()> {
+        let path = transactions_path(params_dir);
+        init_coins_file(&path)?;
+        let id = next_id(&path)?;
+        append_new_entry(
+            &path,
+            json!({
+                "id": id.to_string(),
+                "hash": hash,
+                "label": label,
+                "network": self.network,
+                "from": tx.from.map(|a| a.to_string()),
+                "to": tx.to.and_then(|to| to.to().copied()).map(|a| a.to_string()),
+                "nonce": tx.nonce,
+                "chainId": tx.chain_id,
+                "value": tx.value.unwrap_or_default().to_string(),
+                "input": tx.input.input().cloned().unwrap_or_default().to_string(),
+                "gasLimit": tx.gas,
+                "maxFeePerGas": tx.max_fee_per_gas.map(|f| f.to_string()),
+                "maxPriorityFeePerGas": tx.max_priority_fee_per_gas.map(|f| f.to_string()),
+                "gasPrice": tx.gas_price.map(|f| f.to_string()),
+                "replaces": replaces,
+                "status": "pending",
+            }),
+        )
+    }
+
+    /// Re-signs a pending transaction at the same nonce with bumped fees. When
+    /// `cancel` is set, the replacement is an empty transfer to ourselves.
+    async fn replace_tx(&self, params_dir: &Path, hash: &str, cancel: bool) -> Result<()> {
+        let entry = find_tx(params_dir, hash)?;
+        if entry_str(&entry, "status")? != "pending" {
+            bail!("Transaction {} is not pending anymore", hash);
+        }
+        let net = self.overridden_network()?;
+        let provider = ProviderBuilder::new()
+            .wallet(self.private_key.clone())
+            .connect_http(net.rpc.clone());
+
+        if let Some(receipt) = provider.get_transaction_receipt(hash.parse()?).await? {
+            let status = if receipt.status() { "mined" } else { "failed" };
+            update_tx_status(params_dir, hash, status)?;
+            println!("Transaction {} has already been {}", hash, status);
+            return Ok(());
+        }
+
+        let from = self.private_key.address();
+        let stored_from: Option<Address> =
+            entry_str(&entry, "from").ok().map(str::parse).transpose()?;
+        if stored_from.is_some_and(|stored_from| stored_from != from) {
+            bail!("Transaction {} was not sent by {}", hash, from);
+        }
+        let nonce = entry
+            .get("nonce")
+            .and_then(Value::as_u64)
+            .context("nonce not found in the transaction entry")?;
+        let chain_id = provider.get_chain_id().await?;
+        let tx = if cancel {
+            TransactionRequest::default()
+                .with_to(from)
+                .with_value(U256::ZERO)
+                .with_gas_limit(21_000)
+        } else {
+            let to: Address = entry_str(&entry, "to")?.parse()?;
+            let value: U256 = entry_str(&entry, "value")?.parse()?;
+            let input: Bytes = entry_str(&entry, "input")?.parse()?;
+            let gas_limit = entry
+                .get("gasLimit")
+                .and_then(Value::as_u64)
+                .context("gasLimit not found in the transaction entry")?;
+            TransactionRequest::default()
+                .with_to(to)
+                .with_value(value)
+                .with_input(input)
+                .with_gas_limit(gas_limit)
+        }
+        .with_from(from)
+        .with_nonce(nonce)
+        .with_chain_id(chain_id);
+
+        // Pay at least the bumped old fees, or the current estimate if higher
+        let estimate = self.gas_opt.gas_price(&provider).await?;
+        let tx = match (entry_u128(&entry, "gasPrice")?, estimate) {
+            (Some(old_gas_price), GasPrice::Legacy { gas_price }) => {
+                tx.with_gas_price(bump(old_gas_price).max(gas_price))
+            }
+            (
+                None,
+                GasPrice::Eip1559 {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                },
+            ) => {
+                let old_max_fee = entry_u128(&entry, "maxFeePerGas")?.unwrap_or_default();
+                let old_priority = entry_u128(&entry, "maxPriorityFeePerGas")?.unwrap_or_default();
+                tx.with_max_fee_per_gas(bump(old_max_fee).max(max_fee_per_gas))
+                    .with_max_priority_fee_per_gas(bump(old_priority).max(max_priority_fee_per_gas))
+            }
+            _ => bail!(
+                "Can't replace transaction {} with a different transaction type",
+                hash
+            ),
+        };
+        let replacement_price = match (tx.max_fee_per_gas, tx.gas_price) {
+            (Some(max_fee), _) => GasPrice::Eip1559 {
+                max_fee_per_gas: max_fee,
+                max_priority_fee_per_gas: tx.max_priority_fee_per_gas.unwrap_or_default(),
+            },
+            (None, gas_price) => GasPrice::Legacy {
+                gas_price: gas_price.unwrap_or_default(),
+            },
+        };
+        self.gas_opt
+            .check_gas_spend(tx.gas.unwrap_or_default(), &replacement_price)?;
+
+        let label = if cancel {
+            "cancel".to_string()
+        } else {
+            format!("{} (speedup)", entry_str(&entry, "label").unwrap_or("-"))
+        };
+        let pending = provider.send_transaction(tx.clone()).await?;
+        let new_hash = pending.tx_hash().to_string();
+        println!("Sent replacement {} for {}", new_hash, hash);
+        update_tx_status(params_dir, hash, "replaced")?;
+        self.record_tx(params_dir, &new_hash, &tx, &label, Some(hash))?;
+        let receipt = self.wait_tracked(params_dir, pending, &new_hash).await?;
+        if receipt.status() {
+            println!("Success!");
+        } else {
+            println!("Transaction failed!");
+        }
+        Ok(())
+    }
+}
+
+fn update_tx_status(params_dir: &Path, hash: &str, status: &str) -> Result<()> {
+    let entry = find_tx(params_dir, hash)?;
+    update_entry(
+        transactions_path(params_dir),
+        entry_str(&entry, "id")?,
+        json!({ "status": status }),
+    )
+}
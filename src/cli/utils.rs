@@ -1,9 +1,15 @@
 use crate::fp::Fp;
-use alloy::primitives::U256;
-use anyhow::{Context, Result};
+use crate::utils::BurnCommitment;
+use alloy::primitives::{Address, Bytes, U256};
+use anyhow::{Context, Result, anyhow, bail};
 use ff::PrimeField;
 use serde_json::{Value, json};
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 pub fn check_required_files(params_dir: &std::path::Path) -> Result<(), anyhow::Error> {
     let required_files = [
@@ -42,19 +48,135 @@ pub fn coins_file(
 pub fn burn_file(
     coin_id: U256,
     burn_key: Fp,
-    fee: U256,
     network: &str,
-    spend: U256,
+    amount: U256,
+    burn_address: Address,
+    byte_security_relax: u8,
+    commitment: &BurnCommitment,
 ) -> Result<Value> {
     Ok(json!({
         "id": coin_id.to_string(),
         "burnKey": U256::from_le_bytes(burn_key.to_repr().0).to_string(),
-        "fee": fee.to_string(),
-        "spend":spend.to_string(),
+        "fee": commitment.broadcaster_fee.to_string(),
+        "spend": commitment.spend.to_string(),
+        "amount": amount.to_string(),
+        "burnAddress": burn_address.to_string(),
+        "byteSecurityRelax": byte_security_relax,
+        "receiver": commitment.receiver.to_string(),
+        "receiverHook": commitment.receiver_hook.to_string(),
+        "prover": commitment.prover.map(|prover| prover.to_string()),
+        "proverFee": commitment.prover_fee.to_string(),
+        "extraCommitment": commitment.extra_commitment().to_string(),
         "network": network,
+        "status": BurnStatus::Pending.as_str(),
     }))
 }
 
+/// Lifecycle of an entry in burn.json. Every stage is persisted so that
+/// `burn resume` can continue from wherever the previous run stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BurnStatus {
+    /// Burn key and address are stored, the transfer is not confirmed yet
+    Pending,
+    /// ETH has been sent to the burn address
+    Sent,
+    /// A proof-of-burn has been generated and stored
+    Proved,
+    /// `mintCoin` has been executed successfully
+    Minted,
+}
+
+impl BurnStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BurnStatus::Pending => "pending",
+            BurnStatus::Sent => "sent",
+            BurnStatus::Proved => "proved",
+            BurnStatus::Minted => "minted",
+        }
+    }
+
+    /// Entries written before the status field existed are treated as sent,
+    /// since the old burn command only stored them after the transfer.
+    pub fn of(entry: &Value) -> Result<Self> {
+        match entry.get("status").and_then(Value::as_str) {
+            None => Ok(BurnStatus::Sent),
+            Some(s) => BurnStatus::from_str(s),
+        }
+    }
+}
+
+impl FromStr for BurnStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pending" => Ok(BurnStatus::Pending),
+            "sent" => Ok(BurnStatus::Sent),
+            "proved" => Ok(BurnStatus::Proved),
+            "minted" => Ok(BurnStatus::Minted),
+            _ => bail!("unknown burn status: {}", s),
+        }
+    }
+}
+
+fn entry_has_id(entry: &Value, id: &str) -> bool {
+    entry.get("id").map_or(false, |v| match v {
+        Value::String(s) => s == id,
+        Value::Number(n) => n.to_string() == id,
+        _ => false,
+    })
+}
+
+pub fn read_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Value>, anyhow::Error> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&data)
+        .with_context(|| format!("failed to parse {} as a JSON array", path.display()))
+}
+
+pub fn find_entry<P: AsRef<Path>>(path: P, id: &str) -> Result<Value, anyhow::Error> {
+    let path = path.as_ref();
+    read_entries(path)?
+        .into_iter()
+        .find(|obj| entry_has_id(obj, id))
+        .ok_or_else(|| anyhow!("no entry with id {} found in {}", id, path.display()))
+}
+
+/// Merges the fields of `fields` into the entry with the given id.
+pub fn update_entry<P: AsRef<Path>>(path: P, id: &str, fields: Value) -> Result<(), anyhow::Error> {
+    let path = path.as_ref();
+    let mut entries = read_entries(path)?;
+    let entry = entries
+        .iter_mut()
+        .find(|obj| entry_has_id(obj, id))
+        .ok_or_else(|| anyhow!("no entry with id {} found in {}", id, path.display()))?;
+    let obj = entry
+        .as_object_mut()
+        .with_context(|| format!("entry {} in {} is not an object", id, path.display()))?;
+    let fields = fields
+        .as_object()
+        .with_context(|| "entry update must be a JSON object")?;
+    for (k, v) in fields {
+        obj.insert(k.clone(), v.clone());
+    }
+    let pretty = serde_json::to_string_pretty(&entries)
+        .with_context(|| "failed to serialize updated JSON")?;
+    fs::write(path, pretty).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+pub fn entry_str<'a>(entry: &'a Value, field: &str) -> Result<&'a str, anyhow::Error> {
+    match entry.get(field) {
+        Some(Value::String(s)) => Ok(s),
+        _ => bail!("{} not found in the entry", field),
+    }
+}
+
 pub fn next_id<P: AsRef<Path>>(coins_path: P) -> Result<U256, anyhow::Error> {
     let path = coins_path.as_ref();
 
@@ -103,3 +225,162 @@ pub fn append_new_entry<P: AsRef<Path>>(coins_path: P, entry: Value) -> Result<(
     fs::write(path, pretty).with_context(|| format!("failed to write {}", path.display()))?;
     Ok(())
 }
+
+pub fn entry_burn_key(entry: &Value) -> Result<Fp, anyhow::Error> {
+    let burn_key = entry_str(entry, "burnKey")?;
+    Fp::from_str_vartime(burn_key).ok_or_else(|| anyhow!("invalid burnKey: {}", burn_key))
+}
+
+pub fn burn_proof_path(params_dir: &Path, burn_id: &str) -> PathBuf {
+    params_dir
+        .join("proofs")
+        .join(format!("burn_{}.json", burn_id))
+}
+
+/// Burns stored by older versions always minted to the burning wallet.
+pub fn entry_receiver(entry: &Value, wallet: Address) -> Result<Address, anyhow::Error> {
+    match entry.get("receiver").and_then(Value::as_str) {
+        Some(receiver) => receiver
+            .parse()
+            .with_context(|| format!("invalid receiver: {}", receiver)),
+        None => Ok(wallet),
+    }
+}
+
+/// Burns stored by older versions never paid a prover.
+pub fn entry_prover_fee(entry: &Value) -> Result<U256, anyhow::Error> {
+    match entry.get("proverFee").and_then(Value::as_str) {
+        Some(fee) => fee
+            .parse()
+            .with_context(|| format!("invalid proverFee: {}", fee)),
+        None => Ok(U256::ZERO),
+    }
+}
+
+/// Burns stored by older versions never had a post-mint hook.
+pub fn entry_receiver_hook(entry: &Value) -> Result<Bytes, anyhow::Error> {
+    match entry.get("receiverHook").and_then(Value::as_str) {
+        Some(hook) => hook
+            .parse()
+            .with_context(|| format!("invalid receiverHook: {}", hook)),
+        None => Ok(Bytes::new()),
+    }
+}
+
+/// Everything the burn commits to, with the defaults of older versions for
+/// the fields they didn't store. The prover is only known once chosen.
+pub fn entry_commitment(entry: &Value, wallet: Address) -> Result<BurnCommitment, anyhow::Error> {
+    let prover = match entry.get("prover").and_then(Value::as_str) {
+        Some(prover) => Some(
+            prover
+                .parse()
+                .with_context(|| format!("invalid prover: {}", prover))?,
+        ),
+        None => None,
+    };
+    Ok(BurnCommitment {
+        receiver: entry_receiver(entry, wallet)?,
+        receiver_hook: entry_receiver_hook(entry)?,
+        broadcaster_fee: entry_str(entry, "fee")?.parse()?,
+        prover_fee: entry_prover_fee(entry)?,
+        spend: entry_str(entry, "spend")?.parse()?,
+        prover,
+    })
+}
+
+/// Amount the proof of the burn commits to: the one given when proving it, or
+/// the amount of the burn.
+pub fn entry_intended_amount(entry: &Value) -> Result<Option<U256>, anyhow::Error> {
+    match entry
+        .get("intendedAmount")
+        .or_else(|| entry.get("amount"))
+        .and_then(Value::as_str)
+    {
+        Some(amount) => Ok(Some(
+            amount
+                .parse()
+                .with_context(|| format!("invalid amount: {}", amount))?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Time before which the burn must not be proved and minted, if scheduled.
+pub fn entry_mint_after(entry: &Value) -> Result<Option<SystemTime>, anyhow::Error> {
+    match entry.get("mintAfter") {
+        None => Ok(None),
+        Some(v) => v
+            .as_u64()
+            .map(|secs| Some(UNIX_EPOCH + Duration::from_secs(secs)))
+            .with_context(|| format!("invalid mintAfter: {}", v)),
+    }
+}
+
+/// Entries written by older versions don't carry the burn address.
+pub fn entry_burn_address(entry: &Value) -> Result<Option<Address>, anyhow::Error> {
+    match entry.get("burnAddress").and_then(Value::as_str) {
+        Some(addr) => {
+            Ok(Some(addr.parse().with_context(|| {
+                format!("invalid burnAddress: {}", addr)
+            })?))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Entries written by older versions don't carry the extra commitment.
+pub fn entry_extra_commitment(entry: &Value) -> Result<Option<U256>, anyhow::Error> {
+    match entry.get("extraCommitment").and_then(Value::as_str) {
+        Some(commitment) => {
+            Ok(Some(commitment.parse().with_context(|| {
+                format!("invalid extraCommitment: {}", commitment)
+            })?))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Fails unless the burn address and extra commitment derived from the
+/// parameters of a burn are the ones it was made with, listing what differs.
+pub fn check_burn_commitment(
+    expected_address: Option<Address>,
+    expected_extra_commitment: Option<U256>,
+    burn_address: Address,
+    extra_commitment: U256,
+) -> Result<(), anyhow::Error> {
+    let mut diff = Vec::new();
+    if let Some(expected) = expected_address
+        && expected != burn_address
+    {
+        diff.push(format!(
+            "  burn address:     expected {}, derived {}",
+            expected, burn_address
+        ));
+    }
+    if let Some(expected) = expected_extra_commitment
+        && expected != extra_commitment
+    {
+        diff.push(format!(
+            "  extra commitment: expected {}, derived {}",
+            expected, extra_commitment
+        ));
+    }
+    if !diff.is_empty() {
+        bail!(
+            "The burn-key and parameters don't reproduce the burn (check the receiver, fees, spend and hook):\n{}",
+            diff.join("\n")
+        );
+    }
+    Ok(())
+}
+
+/// Burns stored by older versions were always made without relaxing.
+pub fn entry_byte_security_relax(entry: &Value) -> Result<u8, anyhow::Error> {
+    match entry.get("byteSecurityRelax") {
+        None => Ok(0),
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u8::try_from(v).ok())
+            .with_context(|| format!("invalid byteSecurityRelax: {}", v)),
+    }
+}
@@ -0,0 +1,117 @@
+use super::burn::{resume_burn, unfinished_burns};
+use super::utils::{
+    BurnStatus, check_required_files, entry_burn_address, entry_mint_after, find_entry,
+};
+use super::{BlockOpt, CommonOpt};
+use crate::utils::proving_block_candidate;
+use alloy::providers::Provider;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+pub struct WatchBurnsOpt {
+    #[structopt(flatten)]
+    common_opt: CommonOpt,
+    #[structopt(flatten)]
+    block_opt: BlockOpt,
+    /// Seconds between two scans of the burn addresses
+    #[structopt(long, default_value = "12")]
+    interval: u64,
+    /// Seconds to wait before retrying a burn whose proving or minting failed
+    #[structopt(long, default_value = "600")]
+    retry_after: u64,
+    /// Stop once every burn of the network is minted
+    #[structopt(long)]
+    exit_when_done: bool,
+}
+
+impl WatchBurnsOpt {
+    /// Polls every unminted burn of the network and, once its burn address is
+    /// funded in a block deep enough for `--block-tag` / `--confirmations` and
+    /// its mint schedule is due, proves and mints it like `burn resume` would.
+    pub async fn run(self, params_dir: &Path) -> Result<()> {
+        check_required_files(params_dir)?;
+        let rt = self.common_opt.setup().await?;
+        let mut failures: HashMap<String, Instant> = HashMap::new();
+        println!(
+            "Watching the burns of network {} every {}s...",
+            self.common_opt.network, self.interval
+        );
+        loop {
+            let ids = match unfinished_burns(&self.common_opt, params_dir, None) {
+                Ok(ids) => ids,
+                Err(e) => {
+                    println!(
+                        "Reading the burns failed, retrying in {}s: {:?}",
+                        self.interval, e
+                    );
+                    tokio::time::sleep(Duration::from_secs(self.interval)).await;
+                    continue;
+                }
+            };
+            if ids.is_empty() && self.exit_when_done {
+                println!("Every burn is minted");
+                return Ok(());
+            }
+            for id in ids {
+                if let Some(failed_at) = failures.get(&id)
+                    && failed_at.elapsed() < Duration::from_secs(self.retry_after)
+                {
+                    continue;
+                }
+                match self.is_due(&rt.provider, params_dir, &id).await {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(e) => {
+                        println!(
+                            "Checking burn #{} failed, retrying in {}s: {:?}",
+                            id, self.retry_after, e
+                        );
+                        failures.insert(id, Instant::now());
+                        continue;
+                    }
+                }
+                println!("Burn #{} is funded, minting it...", id);
+                match resume_burn(&self.common_opt, &self.block_opt, params_dir, &id).await {
+                    Ok(()) => {
+                        failures.remove(&id);
+                    }
+                    Err(e) => {
+                        println!(
+                            "Burn #{} failed, retrying in {}s: {:?}",
+                            id, self.retry_after, e
+                        );
+                        failures.insert(id, Instant::now());
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(self.interval)).await;
+        }
+    }
+
+    /// Whether the burn's mint schedule is due and, unless it is proved
+    /// already, its burn address is funded in a block it can be proved against.
+    async fn is_due<P: Provider>(&self, provider: &P, params_dir: &Path, id: &str) -> Result<bool> {
+        let entry = find_entry(params_dir.join("burn.json"), id)?;
+        if entry_mint_after(&entry)?.is_some_and(|t| t > SystemTime::now()) {
+            return Ok(false);
+        }
+        if BurnStatus::of(&entry)? < BurnStatus::Proved {
+            // Entries of older versions lack the address but are sent
+            if let Some(burn_addr) = entry_burn_address(&entry)? {
+                let (_, funded) = proving_block_candidate(
+                    provider,
+                    burn_addr,
+                    self.block_opt.block_tag,
+                    self.block_opt.confirmations,
+                )
+                .await?;
+                return Ok(funded);
+            }
+        }
+        Ok(true)
+    }
+}
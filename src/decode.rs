@@ -0,0 +1,72 @@
+use crate::networks::Network;
+use crate::utils::{BETH, Staking, WORM};
+use alloy::hex;
+use alloy::primitives::utils::format_ether;
+use alloy::rpc::types::TransactionReceipt;
+use alloy::sol_types::{GenericContractError, SolEventInterface, SolInterface};
+
+/// Turns revert data into something readable: one of the custom errors of
+/// the BETH, WORM or Staking ABIs, a `require` message or a panic.
+pub fn decode_revert(data: &[u8]) -> String {
+    if data.is_empty() {
+        return "no revert data".to_string();
+    }
+    if let Ok(err) = BETH::BETHErrors::abi_decode(data) {
+        return format!("BETH::{:?}", err);
+    }
+    if let Ok(err) = WORM::WORMErrors::abi_decode(data) {
+        return format!("WORM::{:?}", err);
+    }
+    if let Ok(err) = Staking::StakingErrors::abi_decode(data) {
+        return format!("Staking::{:?}", err);
+    }
+    match GenericContractError::abi_decode(data) {
+        Ok(GenericContractError::Revert(revert)) => revert.to_string(),
+        Ok(GenericContractError::Panic(panic)) => panic.to_string(),
+        Err(_) => format!("unknown revert data: 0x{}", hex::encode(data)),
+    }
+}
+
+/// Describes why a contract call failed, decoding its revert data if any.
+pub fn describe_call_error(err: &alloy::contract::Error) -> String {
+    match err.as_revert_data() {
+        Some(data) => decode_revert(&data),
+        None => err.to_string(),
+    }
+}
+
+/// Prints the BETH/WORM token transfers of a mined transaction and warns
+/// about every post-mint hook that failed without reverting the mint.
+pub fn report_receipt_logs(receipt: &TransactionReceipt, net: &Network) {
+    for log in receipt.inner.logs() {
+        if log.address() == net.beth {
+            match BETH::BETHEvents::decode_log(&log.inner).map(|l| l.data) {
+                Ok(BETH::BETHEvents::Transfer(t)) => {
+                    println!(
+                        "BETH transfer: {} -> {}: {} BETH",
+                        t.from,
+                        t.to,
+                        format_ether(t.value)
+                    );
+                }
+                Ok(BETH::BETHEvents::HookFailure(f)) => {
+                    println!(
+                        "Warning: a post-mint hook failed: {}",
+                        decode_revert(&f.returnData)
+                    );
+                }
+                _ => {}
+            }
+        } else if log.address() == net.worm
+            && let Ok(WORM::WORMEvents::Transfer(t)) =
+                WORM::WORMEvents::decode_log(&log.inner).map(|l| l.data)
+        {
+            println!(
+                "WORM transfer: {} -> {}: {} WORM",
+                t.from,
+                t.to,
+                format_ether(t.value)
+            );
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::decode::describe_call_error;
+use alloy::contract::{CallBuilder, CallDecoder};
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::TransactionBuilder;
+use alloy::primitives::U256;
+use alloy::primitives::utils::{format_ether, format_units, parse_ether, parse_units};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use anyhow::{Result, anyhow};
+use structopt::StructOpt;
+
+/// Number of past blocks looked at by eth_feeHistory
+const FEE_HISTORY_BLOCKS: u64 = 10;
+/// Reward percentile used as the suggested priority fee
+const FEE_HISTORY_PERCENTILE: f64 = 50.0;
+
+#[derive(StructOpt, Debug, Clone, Default)]
+pub struct GasOpt {
+    /// Max fee per gas in gwei (default: estimated through eth_feeHistory)
+    #[structopt(long)]
+    max_fee: Option<String>,
+    /// Max priority fee per gas in gwei (default: estimated through eth_feeHistory)
+    #[structopt(long)]
+    priority_fee: Option<String>,
+    /// Refuse to send a transaction that may cost more than this amount of ETH in gas
+    #[structopt(long)]
+    max_gas_spend: Option<String>,
+    /// Send legacy transactions, for chains without EIP-1559
+    #[structopt(long)]
+    legacy: bool,
+    /// Gas limit of contract calls (default: estimated)
+    #[structopt(long)]
+    gas_limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum GasPrice {
+    Eip1559 {
+        max_fee_per_gas: u128,
+        max_priority_fee_per_gas: u128,
+    },
+    Legacy {
+        gas_price: u128,
+    },
+}
+
+impl GasPrice {
+    /// Highest price a single unit of gas may cost.
+    pub fn max_per_gas(&self) -> u128 {
+        match self {
+            GasPrice::Eip1559 {
+                max_fee_per_gas, ..
+            } => *max_fee_per_gas,
+            GasPrice::Legacy { gas_price } => *gas_price,
+        }
+    }
+}
+
+fn parse_gwei(value: &str) -> Result<u128> {
+    let wei: U256 = parse_units(value, "gwei")?.into();
+    Ok(wei.try_into()?)
+}
+
+fn format_gwei(wei: u128) -> String {
+    format_units(U256::from(wei), "gwei").unwrap_or_else(|_| wei.to_string())
+}
+
+impl GasOpt {
+    /// Picks the fees for the next transaction. Explicit `--max-fee` and
+    /// `--priority-fee` always win over the eth_feeHistory estimation; chains
+    /// without a base fee fall back to legacy pricing.
+    pub async fn gas_price<P: Provider>(&self, provider: &P) -> Result<GasPrice> {
+        let max_fee = self.max_fee.as_deref().map(parse_gwei).transpose()?;
+        let priority_fee = self.priority_fee.as_deref().map(parse_gwei).transpose()?;
+
+        let history = if self.legacy {
+            None
+        } else {
+            provider
+                .get_fee_history(
+                    FEE_HISTORY_BLOCKS,
+                    BlockNumberOrTag::Latest,
+                    &[FEE_HISTORY_PERCENTILE],
+                )
+                .await
+                .ok()
+        };
+        let base_fee = history
+            .as_ref()
+            .and_then(|h| h.next_block_base_fee())
+            .filter(|base_fee| *base_fee > 0);
+
+        let (history, base_fee) = match (history, base_fee) {
+            (Some(history), Some(base_fee)) => (history, base_fee),
+            _ => {
+                let gas_price = match max_fee {
+                    Some(max_fee) => max_fee,
+                    None => provider.get_gas_price().await?,
+                };
+                return Ok(GasPrice::Legacy { gas_price });
+            }
+        };
+
+        let max_priority_fee_per_gas = match priority_fee {
+            Some(priority_fee) => priority_fee,
+            None => {
+                let mut rewards = history
+                    .reward
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|r| r.first().copied())
+                    .collect::<Vec<_>>();
+                rewards.sort();
+                rewards
+                    .get(rewards.len() / 2)
+                    .copied()
+                    .unwrap_or(1_000_000_000)
+            }
+        };
+        let max_fee_per_gas = match max_fee {
+            Some(max_fee) => max_fee,
+            // Stays valid through several blocks of rising base fees
+            None => 2 * base_fee + max_priority_fee_per_gas,
+        };
+        if max_priority_fee_per_gas > max_fee_per_gas {
+            return Err(anyhow!(
+                "Priority fee ({} gwei) can't be higher than the max fee ({} gwei)!",
+                format_gwei(max_priority_fee_per_gas),
+                format_gwei(max_fee_per_gas)
+            ));
+        }
+        Ok(GasPrice::Eip1559 {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        })
+    }
+
+    /// Makes sure `gas_limit` units of gas can't cost more than `--max-gas-spend`.
+    pub fn check_gas_spend(&self, gas_limit: u64, gas_price: &GasPrice) -> Result<()> {
+        let worst_case = U256::from(gas_limit) * U256::from(gas_price.max_per_gas());
+        println!(
+            "Gas: limit {}, max {} gwei/gas, up to {} ETH",
+            gas_limit,
+            format_gwei(gas_price.max_per_gas()),
+            format_ether(worst_case)
+        );
+        if let Some(max_gas_spend) = &self.max_gas_spend {
+            let max_gas_spend = parse_ether(max_gas_spend)?;
+            if worst_case > max_gas_spend {
+                return Err(anyhow!(
+                    "Transaction may cost up to {} ETH in gas, more than --max-gas-spend {} ETH!",
+                    format_ether(worst_case),
+                    format_ether(max_gas_spend)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Gas limit given through `--gas-limit`, if any.
+    pub fn gas_limit(&self) -> Option<u64> {
+        self.gas_limit
+    }
+
+    /// Estimates the gas limit and sets the fees of a plain transaction.
+    pub async fn apply<P: Provider>(
+        &self,
+        provider: &P,
+        tx: TransactionRequest,
+    ) -> Result<TransactionRequest> {
+        let gas_limit = provider.estimate_gas(tx.clone()).await?;
+        let gas_price = self.gas_price(provider).await?;
+        self.check_gas_spend(gas_limit, &gas_price)?;
+        let tx = tx.with_gas_limit(gas_limit);
+        Ok(match gas_price {
+            GasPrice::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => tx
+                .with_max_fee_per_gas(max_fee_per_gas)
+                .with_max_priority_fee_per_gas(max_priority_fee_per_gas),
+            GasPrice::Legacy { gas_price } => tx.with_gas_price(gas_price),
+        })
+    }
+
+    /// Same as `apply`, for contract calls. A reverting call fails here, with
+    /// its decoded revert reason.
+    pub async fn apply_to_call<P: Provider, Q: Provider, D: CallDecoder>(
+        &self,
+        provider: &Q,
+        call: CallBuilder<P, D>,
+    ) -> Result<CallBuilder<P, D>> {
+        let gas_limit = match self.gas_limit {
+            Some(gas_limit) => gas_limit,
+            None => call
+                .estimate_gas()
+                .await
+                .map_err(|err| anyhow!("Call reverted: {}", describe_call_error(&err)))?,
+        };
+        self.apply_to_call_with_gas_limit(provider, call, gas_limit)
+            .await
+    }
+
+    /// Sets the fees of a contract call whose gas limit is already known.
+    pub async fn apply_to_call_with_gas_limit<P: Provider, Q: Provider, D: CallDecoder>(
+        &self,
+        provider: &Q,
+        call: CallBuilder<P, D>,
+        gas_limit: u64,
+    ) -> Result<CallBuilder<P, D>> {
+        let gas_price = self.gas_price(provider).await?;
+        self.check_gas_spend(gas_limit, &gas_price)?;
+        let call = call.gas(gas_limit);
+        Ok(match gas_price {
+            GasPrice::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => call
+                .max_fee_per_gas(max_fee_per_gas)
+                .max_priority_fee_per_gas(max_priority_fee_per_gas),
+            GasPrice::Legacy { gas_price } => call.gas_price(gas_price),
+        })
+    }
+}
@@ -0,0 +1,342 @@
+use alloy::primitives::utils::{format_ether, parse_ether};
+use alloy::primitives::{Address, Bytes, I256, U160, U256, address};
+use alloy::providers::DynProvider;
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolValue};
+use anyhow::{Context, Result, anyhow, bail};
+use futures::future::BoxFuture;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+sol! {
+    #[sol(rpc)]
+    interface IUniswapV3Pool {
+        /// swap function
+        function swap(
+            address recipient,
+            bool zeroForOne,
+            int256 amountSpecified,
+            uint160 sqrtPriceLimitX96,
+            bytes calldata data
+        ) external returns (int256 amount0, int256 amount1);
+
+        function slot0() external view returns (
+            uint160 sqrtPriceX96,
+            int24 tick,
+            uint16 observationIndex,
+            uint16 observationCardinality,
+            uint16 observationCardinalityNext,
+            uint8 feeProtocol,
+            bool unlocked
+        );
+    }
+
+    /// Participates in WORM epochs with the BETH it is handed, crediting
+    /// `receiver` instead of the caller
+    interface IParticipationRelay {
+        function participateFor(
+            address receiver,
+            uint256 amountPerEpoch,
+            uint256 numEpochs
+        ) external;
+    }
+}
+
+/// BETH/ETH pool the swap hook trades in
+const BETH_ETH_POOL: Address = address!("0x646b5eB499411390448b5e21838aCB8B2FF548dA");
+
+/// What the wallet is minting for, handed to every hook encoder.
+pub struct HookContext {
+    pub provider: DynProvider,
+    /// Receiver of the minted BETH
+    pub receiver: Address,
+    /// Amount of BETH minted by the burn
+    pub spend: U256,
+}
+
+/// A post-mint hook: `amount` of the minted BETH is handed to `target`, which
+/// is then called with `calldata`.
+pub struct HookCall {
+    pub target: Address,
+    pub amount: U256,
+    pub calldata: Bytes,
+}
+
+impl HookCall {
+    /// The hook bytes committed in the burn address and passed to mintCoin.
+    pub fn abi_encode(&self) -> Bytes {
+        (self.target, self.amount, self.calldata.clone())
+            .abi_encode_params()
+            .into()
+    }
+}
+
+/// Arguments of a hook spec: `swap:0.1,slippage=1%` has `0.1` as positional
+/// and `slippage` as named argument.
+#[derive(Debug, Default)]
+pub struct HookArgs {
+    pub positional: Vec<String>,
+    pub named: HashMap<String, String>,
+}
+
+impl HookArgs {
+    /// The amount of BETH the hook works on, always the first positional.
+    pub fn amount(&self) -> Result<U256> {
+        let amount = self
+            .positional
+            .first()
+            .context("the hook needs an amount, e.g. swap:0.1")?;
+        Ok(parse_ether(amount)?)
+    }
+
+    pub fn named(&self, name: &str) -> Option<&str> {
+        self.named.get(name).map(String::as_str)
+    }
+}
+
+/// Turns a user intent into the call a post-mint hook makes.
+pub trait ReceiverHook: Send + Sync {
+    /// Name used in `--hook <name>:<args>`
+    fn name(&self) -> &'static str;
+    /// Arguments accepted after the name, shown in errors
+    fn usage(&self) -> &'static str;
+    fn encode<'a>(
+        &'a self,
+        ctx: &'a HookContext,
+        args: &'a HookArgs,
+    ) -> BoxFuture<'a, Result<HookCall>>;
+}
+
+/// Swaps BETH to ETH for the receiver, optionally bounded by a slippage limit
+/// relative to the current pool price.
+pub struct SwapHook;
+
+impl ReceiverHook for SwapHook {
+    fn name(&self) -> &'static str {
+        "swap"
+    }
+
+    fn usage(&self) -> &'static str {
+        "swap:<BETH amount>[,slippage=<percent>%]"
+    }
+
+    fn encode<'a>(
+        &'a self,
+        ctx: &'a HookContext,
+        args: &'a HookArgs,
+    ) -> BoxFuture<'a, Result<HookCall>> {
+        Box::pin(async move {
+            let amount = args.amount()?;
+            let sqrt_price_limit = match args.named("slippage") {
+                Some(slippage) => {
+                    let slippage = parse_percent(slippage)?;
+                    let pool = IUniswapV3Pool::new(BETH_ETH_POOL, &ctx.provider);
+                    let sqrt_price = pool.slot0().call().await?.sqrtPriceX96;
+                    // Selling token1 raises the price; stop once it moved by the slippage
+                    let factor = ((1.0 + slippage).sqrt() * 1e9) as u64;
+                    let limit =
+                        U256::from(sqrt_price) * U256::from(factor) / U256::from(1_000_000_000u64);
+                    U160::from(limit).min(MAX_SQRT_PRICE)
+                }
+                None => MAX_SQRT_PRICE,
+            };
+            Ok(HookCall {
+                target: BETH_ETH_POOL,
+                amount,
+                calldata: swap_calldata(amount, ctx.receiver, sqrt_price_limit),
+            })
+        })
+    }
+}
+
+/// Forwards BETH to another address.
+pub struct TransferHook;
+
+impl ReceiverHook for TransferHook {
+    fn name(&self) -> &'static str {
+        "transfer"
+    }
+
+    fn usage(&self) -> &'static str {
+        "transfer:<BETH amount>,to=<address>"
+    }
+
+    fn encode<'a>(
+        &'a self,
+        _ctx: &'a HookContext,
+        args: &'a HookArgs,
+    ) -> BoxFuture<'a, Result<HookCall>> {
+        Box::pin(async move {
+            let to: Address = args.named("to").context("missing to=<address>")?.parse()?;
+            Ok(HookCall {
+                target: to,
+                amount: args.amount()?,
+                calldata: Bytes::new(),
+            })
+        })
+    }
+}
+
+/// Participates in WORM epochs on behalf of the receiver.
+///
+/// `WORM.participate` credits its caller, which is BETH while the hook runs, so
+/// the call goes through a relay contract that participates for the receiver.
+pub struct ParticipateHook;
+
+impl ReceiverHook for ParticipateHook {
+    fn name(&self) -> &'static str {
+        "participate"
+    }
+
+    fn usage(&self) -> &'static str {
+        "participate:<BETH amount>,epochs=<count>,via=<relay address>"
+    }
+
+    fn encode<'a>(
+        &'a self,
+        ctx: &'a HookContext,
+        args: &'a HookArgs,
+    ) -> BoxFuture<'a, Result<HookCall>> {
+        Box::pin(async move {
+            let relay: Address = args
+                .named("via")
+                .context("missing via=<relay address>")?
+                .parse()?;
+            let epochs: u64 = args
+                .named("epochs")
+                .context("missing epochs=<count>")?
+                .parse()?;
+            if epochs == 0 {
+                bail!("epochs must be at least 1");
+            }
+            let num_epochs = U256::from(epochs);
+            let amount_per_epoch = args.amount()? / num_epochs;
+            Ok(HookCall {
+                target: relay,
+                // Whatever doesn't divide evenly into epochs stays with the receiver
+                amount: amount_per_epoch * num_epochs,
+                calldata: IParticipationRelay::participateForCall {
+                    receiver: ctx.receiver,
+                    amountPerEpoch: amount_per_epoch,
+                    numEpochs: num_epochs,
+                }
+                .abi_encode()
+                .into(),
+            })
+        })
+    }
+}
+
+/// Highest sqrt price Uniswap V3 accepts, i.e. no slippage limit
+const MAX_SQRT_PRICE: U160 = U160::from_limbs([0x5d951d5263988d25, 0xefd1fc6a50648849, 0xfffd8963]);
+
+/// Calldata of a Uniswap V3 pool swap of `amount_in` BETH to ETH
+fn swap_calldata(amount_in: U256, recipient: Address, sqrt_price_limit_x96: U160) -> Bytes {
+    IUniswapV3Pool::swapCall {
+        recipient,
+        zeroForOne: false,
+        amountSpecified: I256::from_raw(amount_in),
+        sqrtPriceLimitX96: sqrt_price_limit_x96,
+        data: Bytes::new(),
+    }
+    .abi_encode()
+    .into()
+}
+
+/// Parses `1%`, `1` or `0.5%` as a fraction.
+fn parse_percent(value: &str) -> Result<f64> {
+    let percent: f64 = value.trim_end_matches('%').parse()?;
+    if !(0.0..100.0).contains(&percent) {
+        bail!("slippage must be between 0% and 100%");
+    }
+    Ok(percent / 100.0)
+}
+
+/// Known hook encoders, looked up by name.
+#[derive(Clone)]
+pub struct HookRegistry {
+    hooks: Vec<Arc<dyn ReceiverHook>>,
+}
+
+impl Default for HookRegistry {
+    fn default() -> Self {
+        let mut registry = Self { hooks: Vec::new() };
+        registry.register(Box::new(SwapHook));
+        registry.register(Box::new(TransferHook));
+        registry.register(Box::new(ParticipateHook));
+        registry
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<HookRegistry> = RwLock::new(HookRegistry::default());
+}
+
+/// Makes a hook available to `--hook` for the rest of the process, next to the
+/// built-in ones.
+pub fn register(hook: Box<dyn ReceiverHook>) {
+    REGISTRY.write().unwrap().register(hook);
+}
+
+/// Encodes a `<name>:<args>` spec with the built-in and registered hooks.
+pub async fn encode(spec: &str, ctx: &HookContext) -> Result<Bytes> {
+    let registry = REGISTRY.read().unwrap().clone();
+    registry.encode(spec, ctx).await
+}
+
+impl HookRegistry {
+    /// Adds an encoder, replacing any previous one with the same name.
+    pub fn register(&mut self, hook: Box<dyn ReceiverHook>) {
+        self.hooks.retain(|h| h.name() != hook.name());
+        self.hooks.push(hook.into());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn ReceiverHook> {
+        self.hooks
+            .iter()
+            .find(|h| h.name() == name)
+            .map(|h| h.as_ref())
+    }
+
+    /// Encodes a `<name>:<args>` spec into the hook bytes of a burn.
+    pub async fn encode(&self, spec: &str, ctx: &HookContext) -> Result<Bytes> {
+        let (name, args) = parse_spec(spec);
+        let hook = self.get(name).ok_or_else(|| {
+            anyhow!(
+                "Unknown hook \"{}\", expected one of: {}",
+                name,
+                self.hooks
+                    .iter()
+                    .map(|h| h.usage())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+        let call = hook
+            .encode(ctx, &args)
+            .await
+            .with_context(|| format!("usage: --hook {}", hook.usage()))?;
+        if call.amount > ctx.spend {
+            bail!(
+                "The hook can't use more than the minted amount ({} BETH)!",
+                format_ether(ctx.spend)
+            );
+        }
+        Ok(call.abi_encode())
+    }
+}
+
+fn parse_spec(spec: &str) -> (&str, HookArgs) {
+    let (name, rest) = spec.split_once(':').unwrap_or((spec, ""));
+    let mut args = HookArgs::default();
+    for arg in rest.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+        match arg.split_once('=') {
+            Some((key, value)) => {
+                args.named.insert(key.to_string(), value.to_string());
+            }
+            None => args.positional.push(arg.to_string()),
+        }
+    }
+    (name, args)
+}
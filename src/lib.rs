@@ -0,0 +1,150 @@
+mod decode;
+mod fp;
+mod gas;
+pub mod hooks;
+mod poseidon;
+mod pow;
+mod public_inputs;
+
+use cli::RecoverOpt;
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use structopt::StructOpt;
+pub mod cli;
+pub mod server;
+use crate::server::run_server;
+pub mod constants;
+pub mod networks;
+use crate::cli::{
+    BurnOpt, ClaimOpt, GenerateWitnessOpt, InfoOpt, LsCommand, MineOpt, ParticipateOpt,
+    PowBenchOpt, SpendCommand, TxCommand, WatchBurnsOpt,
+};
+mod utils;
+use crate::utils::{RapidsnarkOutput, RapidsnarkProof};
+
+#[derive(StructOpt)]
+pub enum MinerOpt {
+    Info(InfoOpt),
+    Ls(LsCommand),
+    Spend(SpendCommand),
+    Participate(ParticipateOpt),
+    Claim(ClaimOpt),
+    Rapidsnark {
+        #[structopt(long)]
+        zkey: PathBuf,
+        #[structopt(long)]
+        witness: PathBuf,
+        #[structopt(long, value_name = "FILE", default_value = "rapidsnark_output.json")]
+        out: PathBuf,
+    },
+    GenerateWitness(GenerateWitnessOpt),
+    Burn(BurnOpt),
+    Mine(MineOpt),
+    Recover(RecoverOpt),
+    PowBench(PowBenchOpt),
+    Tx(TxCommand),
+    WatchBurns(WatchBurnsOpt),
+    Server,
+}
+
+/// Subcommand run when a command is directly followed by its flags, so that
+/// `burn --amount ..` still means `burn run --amount ..`
+const DEFAULT_SUBCOMMANDS: &[(&str, &str)] = &[("burn", "run"), ("spend", "run")];
+
+impl MinerOpt {
+    /// Parses the command line, filling in the default subcommand of the
+    /// commands that have one.
+    pub fn from_args_with_defaults() -> Self {
+        Self::from_iter(with_default_subcommands(std::env::args_os().collect()))
+    }
+
+    pub async fn run(self, params_dir: &std::path::Path) -> Result<(), anyhow::Error> {
+        match self {
+            MinerOpt::Burn(cmd) => cmd.run(params_dir).await,
+            MinerOpt::Spend(cmd) => cmd.run(params_dir).await,
+            MinerOpt::Ls(cmd) => cmd.run(params_dir).await,
+            MinerOpt::GenerateWitness(cmd) => cmd.run().await,
+            MinerOpt::Info(cmd) => cmd.run().await,
+            MinerOpt::Claim(cmd) => cmd.run(params_dir).await,
+            MinerOpt::Participate(cmd) => cmd.run(params_dir).await,
+            MinerOpt::Mine(cmd) => cmd.run(params_dir).await,
+            MinerOpt::Rapidsnark { zkey, witness, out } => {
+                let params = std::fs::read(zkey)?;
+                let witness = std::fs::read(witness)?;
+                let proof = worm_witness_gens::rapidsnark(&params, &witness)?;
+                let proof_proof: crate::RapidsnarkProof = serde_json::from_str(&proof.proof)?;
+                let proof_public: Vec<alloy::primitives::U256> =
+                    serde_json::from_str(&proof.public)?;
+                let output = crate::RapidsnarkOutput {
+                    proof: proof_proof,
+                    public: proof_public,
+                };
+                let json = serde_json::to_string_pretty(&output)?;
+                std::fs::write(&out, json.as_bytes())?;
+                println!("💾 Saved RapidsnarkOutput to: {}", out.display());
+
+                Ok(())
+            }
+
+            MinerOpt::Recover(cmd) => cmd.run(params_dir).await,
+            MinerOpt::PowBench(cmd) => cmd.run().await,
+            MinerOpt::Tx(cmd) => cmd.run(params_dir).await,
+            MinerOpt::WatchBurns(cmd) => cmd.run(params_dir).await,
+            MinerOpt::Server => {
+                println!("🚀 Starting server...");
+                run_server().await
+            }
+        }
+    }
+}
+
+fn with_default_subcommands(mut args: Vec<OsString>) -> Vec<OsString> {
+    if let Some(command) = args.get(1).and_then(|a| a.to_str())
+        && let Some((_, default)) = DEFAULT_SUBCOMMANDS.iter().find(|(c, _)| *c == command)
+        && args
+            .get(2)
+            .and_then(|a| a.to_str())
+            .is_some_and(|a| a.starts_with("--") && a != "--help")
+    {
+        args.insert(2, default.into());
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<OsString> {
+        line.split(' ').map(OsString::from).collect()
+    }
+
+    #[test]
+    fn flat_spend_runs_a_single_spend() {
+        assert_eq!(
+            with_default_subcommands(args("worm-miner spend --amount 1 --receiver 0x1")),
+            args("worm-miner spend run --amount 1 --receiver 0x1")
+        );
+        assert_eq!(
+            with_default_subcommands(args("worm-miner spend batch payouts.csv")),
+            args("worm-miner spend batch payouts.csv")
+        );
+        assert_eq!(
+            with_default_subcommands(args("worm-miner spend --help")),
+            args("worm-miner spend --help")
+        );
+    }
+
+    #[test]
+    fn flat_burn_runs_send_prove_and_mint() {
+        assert_eq!(
+            with_default_subcommands(args("worm-miner burn --amount 1 --spend 0.9")),
+            args("worm-miner burn run --amount 1 --spend 0.9")
+        );
+        assert_eq!(
+            with_default_subcommands(args("worm-miner burn mint 1")),
+            args("worm-miner burn mint 1")
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use crate::fp::Fp;
+use alloy::primitives::{U256, keccak256};
+use anyhow::{Result, anyhow};
+use ff::{Field, PrimeField};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+
+/// Number of zero bytes the proof-of-burn circuit requires from the burn-key
+/// hash when `byteSecurityRelax` is zero. Every relaxed byte adds one more.
+pub const POW_MIN_ZERO_BYTES: usize = 2;
+
+/// Hashes tried by a worker between two checks of the shared flags
+const BATCH_SIZE: u64 = 4096;
+
+pub fn pow_hash(burn_key: U256, reveal: U256, burn_extra_commit: U256) -> U256 {
+    let mut inp: [u8; 104] = [0; 104];
+    inp[..32].copy_from_slice(&burn_key.to_be_bytes::<32>());
+    inp[32..64].copy_from_slice(&reveal.to_be_bytes::<32>());
+    inp[64..96].copy_from_slice(&burn_extra_commit.to_be_bytes::<32>());
+    inp[96..].copy_from_slice(b"EIP-7503");
+    keccak256(inp).into()
+}
+
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Average number of hashes needed to find a burn-key.
+pub fn expected_hashes(pow_min_zero_bytes: usize) -> f64 {
+    2f64.powi(8 * pow_min_zero_bytes as i32)
+}
+
+/// Searches for a burn-key on `threads` threads, each starting at its own
+/// random point. Returns `None` if `cancel` gets set before a key is found.
+pub fn find_burn_key(
+    pow_min_zero_bytes: usize,
+    burn_extra_commit: U256,
+    reveal: U256,
+    threads: usize,
+    cancel: &AtomicBool,
+) -> Option<Fp> {
+    let found = AtomicBool::new(false);
+    let hashes = AtomicU64::new(0);
+    let (tx, rx) = mpsc::channel();
+    let start = Instant::now();
+    let expected = expected_hashes(pow_min_zero_bytes);
+
+    std::thread::scope(|s| {
+        for _ in 0..threads.max(1) {
+            let tx = tx.clone();
+            let (found, hashes) = (&found, &hashes);
+            s.spawn(move || {
+                let mut curr: U256 =
+                    U256::from_le_bytes(Fp::random(ff::derive::rand_core::OsRng).to_repr().0);
+                while !found.load(Ordering::Relaxed) && !cancel.load(Ordering::Relaxed) {
+                    for _ in 0..BATCH_SIZE {
+                        let hash = pow_hash(curr, reveal, burn_extra_commit);
+                        if hash.leading_zeros() >= pow_min_zero_bytes * 8 {
+                            found.store(true, Ordering::Relaxed);
+                            let _ = tx.send(Fp::from_be_bytes(&curr.to_be_bytes::<32>()));
+                            return;
+                        }
+                        curr += U256::ONE;
+                    }
+                    hashes.fetch_add(BATCH_SIZE, Ordering::Relaxed);
+                }
+            });
+        }
+        drop(tx);
+
+        // Progress reporting, until every worker has stopped
+        loop {
+            match rx.recv_timeout(Duration::from_secs(5)) {
+                Ok(burn_key) => return Some(burn_key),
+                Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    let done = hashes.load(Ordering::Relaxed) as f64;
+                    let rate = done / start.elapsed().as_secs_f64();
+                    println!(
+                        "PoW: {:.1}M hashes ({:.2} MH/s), expected total {:.1}M, ETA ~{}s",
+                        done / 1e6,
+                        rate / 1e6,
+                        expected / 1e6,
+                        ((expected - done).max(0.0) / rate.max(1.0)) as u64
+                    );
+                }
+            }
+        }
+    })
+}
+
+/// Cancel flag of the burn-key search in progress, if any.
+static CURRENT_SEARCH: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+static CTRL_C_HANDLER: Once = Once::new();
+
+/// Installs the single Ctrl-C handler of the process: it cancels the search in
+/// progress, or exits as Ctrl-C normally would when no search is running.
+fn install_ctrl_c_handler() {
+    CTRL_C_HANDLER.call_once(|| {
+        tokio::spawn(async {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                match CURRENT_SEARCH.lock().unwrap().as_ref() {
+                    Some(cancel) => {
+                        println!("Cancelling the burn-key search...");
+                        cancel.store(true, Ordering::SeqCst);
+                    }
+                    None => std::process::exit(130),
+                }
+            }
+        });
+    });
+}
+
+/// Runs `find_burn_key` on a blocking thread. Ctrl-C cancels the search; once
+/// the search is over, Ctrl-C exits the process as it normally would.
+pub async fn find_burn_key_cancellable(
+    pow_min_zero_bytes: usize,
+    burn_extra_commit: U256,
+    reveal: U256,
+    threads: usize,
+) -> Result<Fp> {
+    install_ctrl_c_handler();
+    let cancel = Arc::new(AtomicBool::new(false));
+    *CURRENT_SEARCH.lock().unwrap() = Some(cancel.clone());
+    println!(
+        "Searching a burn-key with {} zero bytes on {} threads...",
+        pow_min_zero_bytes, threads
+    );
+    let burn_key = tokio::task::spawn_blocking(move || {
+        find_burn_key(
+            pow_min_zero_bytes,
+            burn_extra_commit,
+            reveal,
+            threads,
+            &cancel,
+        )
+    })
+    .await;
+    *CURRENT_SEARCH.lock().unwrap() = None;
+    burn_key?.ok_or(anyhow!("Burn-key search was cancelled!"))
+}
+
+/// Measures the burn-key hash rate (hashes per second) of `threads` threads.
+pub fn hash_rate(threads: usize, duration: Duration) -> f64 {
+    let stop = AtomicBool::new(false);
+    let hashes = AtomicU64::new(0);
+    let start = Instant::now();
+    std::thread::scope(|s| {
+        for _ in 0..threads.max(1) {
+            let (stop, hashes) = (&stop, &hashes);
+            s.spawn(move || {
+                let mut curr = U256::ZERO;
+                let mut zeros = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    for _ in 0..BATCH_SIZE {
+                        zeros = zeros.max(pow_hash(curr, U256::ZERO, U256::ZERO).leading_zeros());
+                        curr += U256::ONE;
+                    }
+                    hashes.fetch_add(BATCH_SIZE, Ordering::Relaxed);
+                }
+                std::hint::black_box(zeros);
+            });
+        }
+        std::thread::sleep(duration);
+        stop.store(true, Ordering::Relaxed);
+    });
+    hashes.load(Ordering::Relaxed) as f64 / start.elapsed().as_secs_f64()
+}
@@ -0,0 +1,279 @@
+use crate::server::types::{MintRequest, SpendRequest};
+use crate::utils::{RapidsnarkOutput, generate_burn_extra_commit, generate_proof_extra_commit};
+use alloy::primitives::{B256, U256, keccak256};
+use anyhow::{Result, anyhow};
+
+/// Public signals of a circuit. Both circuits expose a single signal: the
+/// commitment BETH recomputes from the arguments of mintCoin / spendCoin
+/// before handing it to the verifier.
+pub trait PublicInputs {
+    const CIRCUIT: &'static str;
+
+    /// The values the commitment is computed from, in hashing order.
+    fn fields(&self) -> Vec<(&'static str, U256)>;
+
+    fn commitment(&self) -> U256 {
+        let preimage = self
+            .fields()
+            .iter()
+            .flat_map(|(_, value)| value.to_be_bytes::<32>())
+            .collect::<Vec<u8>>();
+        U256::from_be_slice(keccak256(preimage).as_slice()) >> U256::from(8)
+    }
+}
+
+/// What a proof-of-burn proof commits to.
+#[derive(Debug, Clone)]
+pub struct BurnPublicInputs {
+    pub block_root: B256,
+    pub nullifier: U256,
+    pub remaining_coin: U256,
+    pub revealed_amount: U256,
+    pub burn_extra_commitment: U256,
+    pub proof_extra_commitment: U256,
+}
+
+impl BurnPublicInputs {
+    /// The values mintCoin is going to hash for `req`, given the hash of the
+    /// proven block.
+    pub fn of_mint(req: &MintRequest, block_root: B256) -> Self {
+        BurnPublicInputs {
+            block_root,
+            nullifier: req.nullifier,
+            remaining_coin: req.remaining_coin,
+            revealed_amount: req.revealed_amount,
+            burn_extra_commitment: generate_burn_extra_commit(
+                req.revealed_amount_receiver,
+                req.prover_fee,
+                req.broadcaster_fee,
+                req.receiver_post_mint_hook.clone(),
+            ),
+            proof_extra_commitment: generate_proof_extra_commit(req.prover),
+        }
+    }
+}
+
+impl PublicInputs for BurnPublicInputs {
+    const CIRCUIT: &'static str = "proof-of-burn";
+
+    // Order of the commitment in the proof-of-burn circuit and BETH.mintCoin
+    fn fields(&self) -> Vec<(&'static str, U256)> {
+        vec![
+            ("block root", self.block_root.into()),
+            ("nullifier", self.nullifier),
+            ("remaining coin", self.remaining_coin),
+            ("revealed amount", self.revealed_amount),
+            ("burn extra commitment", self.burn_extra_commitment),
+            ("proof extra commitment", self.proof_extra_commitment),
+        ]
+    }
+}
+
+/// What a spend proof commits to.
+#[derive(Debug, Clone)]
+pub struct SpendPublicInputs {
+    pub coin: U256,
+    pub revealed_amount: U256,
+    pub remaining_coin: U256,
+    pub broadcaster_fee: U256,
+    pub receiver: U256,
+}
+
+impl SpendPublicInputs {
+    pub fn of_spend(req: &SpendRequest) -> Self {
+        SpendPublicInputs {
+            coin: req.coin,
+            revealed_amount: req.revealed_amount,
+            remaining_coin: req.remaining_coin,
+            broadcaster_fee: req.broadcaster_fee,
+            receiver: U256::from_be_slice(req.receiver.as_slice()),
+        }
+    }
+}
+
+impl PublicInputs for SpendPublicInputs {
+    const CIRCUIT: &'static str = "spend";
+
+    // Order of the commitment in the spend circuit and BETH.spendCoin
+    fn fields(&self) -> Vec<(&'static str, U256)> {
+        vec![
+            ("coin", self.coin),
+            ("revealed amount", self.revealed_amount),
+            ("remaining coin", self.remaining_coin),
+            ("broadcaster fee", self.broadcaster_fee),
+            ("receiver", self.receiver),
+        ]
+    }
+}
+
+/// Decodes the public signals of `proof` and checks that they commit to
+/// `expected`, the values about to be broadcast. A proof made for other
+/// values would only revert on-chain.
+pub fn check_public_inputs<T: PublicInputs>(proof: &RapidsnarkOutput, expected: &T) -> Result<()> {
+    let proved = match proof.public.as_slice() {
+        [commitment] => *commitment,
+        public => {
+            return Err(anyhow!(
+                "A {} proof has 1 public signal, this one has {}",
+                T::CIRCUIT,
+                public.len()
+            ));
+        }
+    };
+    let commitment = expected.commitment();
+    if proved == commitment {
+        return Ok(());
+    }
+    let mut diff = format!(
+        "The {} proof doesn't commit to the values being sent:\n- commitment: {}\n+ commitment: {}\n  computed from:",
+        T::CIRCUIT,
+        proved,
+        commitment
+    );
+    for (name, value) in expected.fields() {
+        diff += &format!("\n    {}: {}", name, value);
+    }
+    Err(anyhow!(diff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::RapidsnarkProof;
+    use alloy::primitives::b256;
+
+    // keccak256 of the 32-byte big-endian fields shifted right by 8 bits, as
+    // described by the circuits. They pin the hashing order, but only the
+    // proof fixtures below check it against the circuits themselves.
+    const BURN_COMMITMENT: &str =
+        "204425913065074020099908070445587739860504718752338339938461932242602355440";
+    const SPEND_COMMITMENT: &str =
+        "256519670972299076433721541298826383105561554520888662591976140045768203035";
+
+    fn burn_inputs() -> BurnPublicInputs {
+        BurnPublicInputs {
+            block_root: b256!("0x1111111111111111111111111111111111111111111111111111111111111111"),
+            nullifier: U256::from(2),
+            remaining_coin: U256::from(3),
+            revealed_amount: U256::from(4),
+            burn_extra_commitment: U256::from(5),
+            proof_extra_commitment: U256::from(6),
+        }
+    }
+
+    fn spend_inputs() -> SpendPublicInputs {
+        SpendPublicInputs {
+            coin: U256::from(7),
+            revealed_amount: U256::from(8),
+            remaining_coin: U256::from(9),
+            broadcaster_fee: U256::from(10),
+            receiver: U256::from(0xaa),
+        }
+    }
+
+    fn proof_with(public: Vec<U256>) -> RapidsnarkOutput {
+        RapidsnarkOutput {
+            proof: RapidsnarkProof {
+                pi_a: [U256::ZERO; 3],
+                pi_b: [[U256::ZERO; 2]; 3],
+                pi_c: [U256::ZERO; 3],
+                protocol: "groth16".to_string(),
+            },
+            public,
+        }
+    }
+
+    #[test]
+    fn burn_commitment_hashes_fields_in_circuit_order() {
+        assert_eq!(
+            burn_inputs().commitment(),
+            BURN_COMMITMENT.parse::<U256>().unwrap()
+        );
+    }
+
+    #[test]
+    fn spend_commitment_hashes_fields_in_circuit_order() {
+        assert_eq!(
+            spend_inputs().commitment(),
+            SPEND_COMMITMENT.parse::<U256>().unwrap()
+        );
+    }
+
+    #[test]
+    fn check_public_inputs_accepts_matching_signal() {
+        let proof = proof_with(vec![BURN_COMMITMENT.parse().unwrap()]);
+        assert!(check_public_inputs(&proof, &burn_inputs()).is_ok());
+    }
+
+    #[test]
+    fn check_public_inputs_rejects_swapped_fields() {
+        let proof = proof_with(vec![BURN_COMMITMENT.parse().unwrap()]);
+        let swapped = BurnPublicInputs {
+            remaining_coin: U256::from(4),
+            revealed_amount: U256::from(3),
+            ..burn_inputs()
+        };
+        let err = check_public_inputs(&proof, &swapped).unwrap_err();
+        assert!(err.to_string().contains("remaining coin: 4"));
+    }
+
+    #[test]
+    fn check_public_inputs_rejects_extra_signals() {
+        let proof = proof_with(vec![U256::ZERO, U256::ZERO]);
+        assert!(check_public_inputs(&proof, &spend_inputs()).is_err());
+    }
+
+    /// Public signal and inputs of a real proof, stored by circuit in
+    /// `src/fixtures/<circuit>.json` as `{"public": [..], "inputs": [..]}`
+    /// with the inputs in hashing order. Take them from a `burn prove` /
+    /// `spend` run against anvil: `public` from the rapidsnark output and
+    /// `inputs` from the circuit's input.json.
+    fn fixture(circuit: &str) -> (Vec<U256>, Vec<U256>) {
+        let path = format!(
+            "{}/src/fixtures/{}.json",
+            env!("CARGO_MANIFEST_DIR"),
+            circuit
+        );
+        let data = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("missing proof fixture {}: {}", path, e));
+        let json: serde_json::Value = serde_json::from_str(&data).unwrap();
+        let values = |key: &str| -> Vec<U256> {
+            json[key]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| v.as_str().unwrap().parse().unwrap())
+                .collect()
+        };
+        (values("public"), values("inputs"))
+    }
+
+    #[test]
+    #[ignore = "needs src/fixtures/proof-of-burn.json from a real proof"]
+    fn burn_commitment_matches_a_real_proof() {
+        let (public, inputs) = fixture(BurnPublicInputs::CIRCUIT);
+        let expected = BurnPublicInputs {
+            block_root: inputs[0].into(),
+            nullifier: inputs[1],
+            remaining_coin: inputs[2],
+            revealed_amount: inputs[3],
+            burn_extra_commitment: inputs[4],
+            proof_extra_commitment: inputs[5],
+        };
+        check_public_inputs(&proof_with(public), &expected).unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs src/fixtures/spend.json from a real proof"]
+    fn spend_commitment_matches_a_real_proof() {
+        let (public, inputs) = fixture(SpendPublicInputs::CIRCUIT);
+        let expected = SpendPublicInputs {
+            coin: inputs[0],
+            revealed_amount: inputs[1],
+            remaining_coin: inputs[2],
+            broadcaster_fee: inputs[3],
+            receiver: inputs[4],
+        };
+        check_public_inputs(&proof_with(public), &expected).unwrap();
+    }
+}
@@ -9,7 +9,10 @@ use uuid::Uuid;
 
 use crate::server::{
     queue::QueueError,
-    types::{ApiResponse, AppState, JobResponse, JobStatus, ProofInput, ProofOutput},
+    types::{
+        ApiResponse, AppState, JobResponse, JobStatus, MintRequest, ProofInput, ProofOutput,
+        RelayResponse, SpendRequest,
+    },
 };
 
 pub async fn start_proof(
@@ -145,3 +148,61 @@ pub async fn poll_proof(
         },
     }
 }
+
+pub async fn relay_mint(
+    State(state): State<AppState>,
+    Json(payload): Json<MintRequest>,
+) -> impl IntoResponse {
+    let Some(relayer) = state.relayer.clone() else {
+        return relayer_disabled();
+    };
+    relay_result(relayer.relay_mint(&payload).await, "Mint transaction sent")
+}
+
+pub async fn relay_spend(
+    State(state): State<AppState>,
+    Json(payload): Json<SpendRequest>,
+) -> impl IntoResponse {
+    let Some(relayer) = state.relayer.clone() else {
+        return relayer_disabled();
+    };
+    relay_result(
+        relayer.relay_spend(&payload).await,
+        "Spend transaction sent",
+    )
+}
+
+fn relayer_disabled() -> (StatusCode, Json<ApiResponse<RelayResponse>>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ApiResponse {
+            status: "error".into(),
+            message: "Relayer mode is not enabled on this server".into(),
+            result: None,
+        }),
+    )
+}
+
+fn relay_result(
+    result: anyhow::Result<String>,
+    message: &str,
+) -> (StatusCode, Json<ApiResponse<RelayResponse>>) {
+    match result {
+        Ok(tx_hash) => (
+            StatusCode::OK,
+            Json(ApiResponse {
+                status: "sent".into(),
+                message: message.into(),
+                result: Some(RelayResponse { tx_hash }),
+            }),
+        ),
+        Err(err) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse {
+                status: "error".into(),
+                message: err.to_string(),
+                result: None,
+            }),
+        ),
+    }
+}
@@ -1,6 +1,7 @@
 pub mod handlers;
 pub mod proof_logic;
 pub mod queue;
+pub mod relay;
 pub mod types;
 pub mod verify_proof;
 pub mod worker;
@@ -18,7 +19,7 @@ use axum::{
     Router,
     routing::{get, post},
 };
-pub use handlers::{poll_proof, start_proof};
+pub use handlers::{poll_proof, relay_mint, relay_spend, start_proof};
 use std::net::SocketAddr;
 use std::sync::Arc;
 fn load_env_files() {
@@ -77,10 +78,19 @@ pub async fn run_server() -> Result<()> {
     let (job_queue, receiver) = JobQueue::with_capacity(queue_cap);
     let jobs = Arc::new(dashmap::DashMap::new());
 
+    let relayer = types::Relayer::from_env()?.map(Arc::new);
+    if let Some(relayer) = &relayer {
+        println!(
+            "Relayer mode enabled, broadcasting from {}",
+            relayer.signer.address()
+        );
+    }
+
     let state = AppState {
         jobs: jobs.clone(),
         job_queue: job_queue.clone(),
         params_dir: std::path::PathBuf::from("./params"),
+        relayer,
     };
 
     spawn_job_worker(receiver, jobs.clone(), job_queue.clone());
@@ -93,6 +103,8 @@ pub async fn run_server() -> Result<()> {
     let app = Router::new()
         .route("/proof", post(start_proof))
         .route("/proof/{job_id}", get(poll_proof))
+        .route("/mint", post(relay_mint))
+        .route("/spend", post(relay_spend))
         .with_state(state)
         .layer(cors)
         .layer(trace);
@@ -2,17 +2,19 @@ use crate::constants::poseidon_burn_address_prefix;
 use crate::fp::Fp;
 use crate::server::types::{ProofInput, ProofOutput};
 use crate::server::verify_proof::verify_proof;
-use crate::utils::RapidsnarkOutput;
+use crate::utils::{BurnCommitment, RapidsnarkOutput};
 use crate::utils::{
-    build_and_prove_burn_logic, compute_nullifier, compute_remaining_coin,
+    build_and_prove_burn_logic, check_burn_unminted, compute_nullifier, compute_remaining_coin,
     fetch_block_and_header_bytes, get_account_proof,
 };
 use alloy::hex::FromHex;
 use alloy::primitives::Bytes;
 use alloy::{
-    primitives::{Address, U256, utils::parse_ether},
+    primitives::{
+        Address, U256,
+        utils::{format_ether, parse_ether},
+    },
     providers::{Provider, ProviderBuilder},
-    rpc::types::EIP1186AccountProofResponse,
 };
 use anyhow::{Result, anyhow};
 use ff::PrimeField;
@@ -24,7 +26,6 @@ fn derive_burn_and_nullifier_from_input(
 ) -> Result<(Address, Fp, U256, U256, U256, U256, Address, Fp, U256, U256)> {
     let wallet_addr = Address::from_str(input.wallet_address.trim())
         .map_err(|e| anyhow!("Invalid wallet address: {}", e))?;
-    println!("{:?}", input);
     let broadcaster_fee = parse_ether(&input.broadcaster_fee)?;
     let prover_fee = parse_ether(&input.prover_fee)?;
     let spend = parse_ether(&input.spend)?;
@@ -42,7 +43,6 @@ fn derive_burn_and_nullifier_from_input(
         spend,
         Bytes::from_hex(input.receiver_hook.clone())?,
     );
-    println!("Extra commit: {}", extra_commit.to_string());
 
     let (nullifier_fp, nullifier_u256) = compute_nullifier(burn_key_fp);
 
@@ -63,26 +63,32 @@ fn derive_burn_and_nullifier_from_input(
 async fn gen_input_witness_proof<P: Provider>(
     provider: &P,
     params_dir: &Path,
-    burn_addr: Address,
     burn_key_fp: Fp,
-    spend: U256,
-    burn_extra_commit: U256,
-    prover: Address,
-    proof: Option<EIP1186AccountProofResponse>,
-    block_number: Option<u64>,
+    amount: U256,
+    commitment: &BurnCommitment,
+    input: &ProofInput,
 ) -> Result<(RapidsnarkOutput, u64)> {
+    let (burn_addr, burn_extra_commit) = commitment.burn_address(burn_key_fp);
+    let prover = commitment
+        .prover
+        .ok_or(anyhow!("The proof needs a prover to commit to"))?;
+    let block_number = input.block_number;
     let (block_number_val, header_bytes) =
         fetch_block_and_header_bytes(provider, block_number).await?;
 
-    let proof = match (proof, block_number) {
+    let proof = match (&input.proof, block_number) {
         (Some(p), Some(block_number)) => {
             verify_proof(provider, p.clone(), block_number)
                 .await
                 .map_err(|e| anyhow::anyhow!("Proof verification failed: {:?}", e))?;
-            p
+            p.clone()
+        }
+        (None, _) => get_account_proof(provider, burn_addr, block_number_val).await?,
+        (Some(_), None) => {
+            return Err(anyhow!(
+                "An account proof needs the block_number it was made at"
+            ));
         }
-        (None, None) => get_account_proof(provider, burn_addr).await?,
-        _ => unreachable!(),
     };
     let effective_block_number = block_number.unwrap_or(block_number_val);
 
@@ -90,8 +96,10 @@ async fn gen_input_witness_proof<P: Provider>(
         params_dir,
         header_bytes,
         burn_key_fp,
-        spend,
+        amount,
+        commitment.spend,
         burn_extra_commit,
+        input.byte_security_relax,
         prover,
         "input.json",
         "witness.wtns",
@@ -123,7 +131,7 @@ pub async fn compute_proof(input: ProofInput) -> Result<ProofOutput> {
         burn_addr,
         _nullifier_fp,
         nullifier_u256,
-        burn_extra_commit,
+        _burn_extra_commit,
     ) = derive_burn_and_nullifier_from_input(&input)?;
 
     println!("[compute_proof] Burn address: {:?}", burn_addr);
@@ -134,23 +142,41 @@ pub async fn compute_proof(input: ProofInput) -> Result<ProofOutput> {
     if balance.is_zero() {
         return Err(anyhow!("No ETH present in the burn address"));
     }
+    if balance < amount {
+        return Err(anyhow!(
+            "The burn address holds {} ETH, less than the {} ETH to prove",
+            format_ether(balance),
+            format_ether(amount)
+        ));
+    }
+    check_burn_unminted(&provider, net.beth, burn_key_fp).await?;
 
     let (_remaining_fp, remaining_coin_u256) = compute_remaining_coin(burn_key_fp, amount, spend)?;
+    let prover = match &input.prover {
+        Some(prover) => Address::from_str(prover.trim())
+            .map_err(|e| anyhow!("Invalid prover address: {}", e))?,
+        None => wallet_addr,
+    };
 
     let params_dir = homedir::my_home()?
         .ok_or(anyhow!("Can't find home directory"))?
         .join(".worm-miner");
 
+    let commitment = BurnCommitment {
+        receiver: wallet_addr,
+        receiver_hook: Bytes::from_hex(&input.receiver_hook)?,
+        broadcaster_fee,
+        prover_fee,
+        spend,
+        prover: Some(prover),
+    };
     let (json_output, block_number) = gen_input_witness_proof(
         &provider,
-        &params_dir.as_path(),
-        burn_addr,
+        params_dir.as_path(),
         burn_key_fp,
-        spend,
-        burn_extra_commit,
-        wallet_addr, // TODO: prover
-        input.proof,
-        input.block_number,
+        amount,
+        &commitment,
+        &input,
     )
     .await?;
 
@@ -163,7 +189,7 @@ pub async fn compute_proof(input: ProofInput) -> Result<ProofOutput> {
         remaining_coin: remaining_coin_u256.to_string(),
         broadcaster_fee: broadcaster_fee.to_string(),
         prover_fee: prover_fee.to_string(),
-        prover: wallet_addr.to_string(), // TODO: prover
+        prover: prover.to_string(),
         reveal_amount: spend.to_string(),
         wallet_address: input.wallet_address,
     })
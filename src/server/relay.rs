@@ -0,0 +1,112 @@
+use crate::decode::describe_call_error;
+use crate::gas::{GasOpt, GasPrice};
+use crate::server::types::{MintRequest, Relayer, SpendRequest};
+use crate::utils::BETH;
+use alloy::primitives::U256;
+use alloy::primitives::utils::{format_ether, parse_ether};
+use alloy::providers::ProviderBuilder;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol_types::SolCall;
+use anyhow::{Result, anyhow};
+
+impl Relayer {
+    /// Relayer mode is enabled by setting `RELAYER_PRIVATE_KEY`; the minimum
+    /// broadcaster fee (in ETH) is read from `RELAYER_MIN_FEE`.
+    pub fn from_env() -> Result<Option<Self>> {
+        let signer = match std::env::var("RELAYER_PRIVATE_KEY") {
+            Ok(key) => key.parse::<PrivateKeySigner>()?,
+            Err(_) => return Ok(None),
+        };
+        let min_fee = match std::env::var("RELAYER_MIN_FEE") {
+            Ok(fee) => parse_ether(&fee)?,
+            Err(_) => U256::ZERO,
+        };
+        Ok(Some(Self {
+            signer,
+            min_fee,
+            send_lock: tokio::sync::Mutex::new(()),
+        }))
+    }
+
+    /// Validates a mint through eth_call and broadcasts it, keeping the
+    /// broadcaster fee. Requests whose fee doesn't cover the gas are refused.
+    pub async fn relay_mint(&self, req: &MintRequest) -> Result<String> {
+        self.relay_call(
+            &req.network,
+            req.broadcaster_fee,
+            &req.mint_call(),
+            "mintCoin",
+        )
+        .await
+    }
+
+    /// Same as `relay_mint`, for spendCoin.
+    pub async fn relay_spend(&self, req: &SpendRequest) -> Result<String> {
+        self.relay_call(
+            &req.network,
+            req.broadcaster_fee,
+            &req.spend_call(),
+            "spendCoin",
+        )
+        .await
+    }
+
+    async fn relay_call<C: SolCall>(
+        &self,
+        network: &str,
+        broadcaster_fee: U256,
+        call: &C,
+        name: &str,
+    ) -> Result<String> {
+        let net = crate::networks::NETWORKS
+            .get(network)
+            .ok_or(anyhow!("Network not found!"))?;
+        if broadcaster_fee < self.min_fee {
+            return Err(anyhow!(
+                "Broadcaster fee {} ETH is below the minimum of {} ETH",
+                format_ether(broadcaster_fee),
+                format_ether(self.min_fee)
+            ));
+        }
+        let provider = ProviderBuilder::new()
+            .wallet(self.signer.clone())
+            .connect_http(net.rpc.clone());
+        let beth = BETH::new(net.beth, &provider);
+        let call = beth.call_builder(call);
+
+        call.call_raw()
+            .await
+            .map_err(|e| anyhow!("{} would revert: {}", name, describe_call_error(&e)))?;
+        let gas_limit = call
+            .estimate_gas()
+            .await
+            .map_err(|e| anyhow!("{} would revert: {}", name, describe_call_error(&e)))?;
+
+        let gas_opt = GasOpt::default();
+        let gas_price = gas_opt.gas_price(&provider).await?;
+        let gas_cost = U256::from(gas_limit) * U256::from(gas_price.max_per_gas());
+        if gas_cost > broadcaster_fee {
+            return Err(anyhow!(
+                "Broadcaster fee {} ETH doesn't cover the gas ({} ETH)",
+                format_ether(broadcaster_fee),
+                format_ether(gas_cost)
+            ));
+        }
+
+        let _guard = self.send_lock.lock().await;
+        let call = call.gas(gas_limit);
+        let call = match gas_price {
+            GasPrice::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => call
+                .max_fee_per_gas(max_fee_per_gas)
+                .max_priority_fee_per_gas(max_priority_fee_per_gas),
+            GasPrice::Legacy { gas_price } => call.gas_price(gas_price),
+        };
+        let pending = call.send().await?;
+        let tx_hash = pending.tx_hash().to_string();
+        println!("[relay] Sent {} transaction {}", name, tx_hash);
+        Ok(tx_hash)
+    }
+}
@@ -1,4 +1,7 @@
+use crate::utils::{BETH, RapidsnarkOutput};
+use alloy::primitives::{Address, Bytes, U256};
 use alloy::rpc::types::EIP1186AccountProofResponse;
+use alloy::signers::local::PrivateKeySigner;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -24,9 +27,14 @@ pub struct ProofInput {
     pub proof: Option<EIP1186AccountProofResponse>,
     pub block_number: Option<u64>,
     pub receiver_hook: String,
+    #[serde(default)]
+    pub byte_security_relax: u8,
+    /// Address the proof is made for (default: wallet_address)
+    #[serde(default)]
+    pub prover: Option<String>,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProofOutput {
     pub burn_address: String,
     pub proof: Value,
@@ -40,7 +48,96 @@ pub struct ProofOutput {
     pub wallet_address: String,
 }
 
-#[derive(Serialize)]
+/// Everything a broadcaster needs to submit mintCoin on behalf of a user.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MintRequest {
+    pub network: String,
+    pub proof: RapidsnarkOutput,
+    pub block_number: u64,
+    pub nullifier: U256,
+    pub remaining_coin: U256,
+    pub broadcaster_fee: U256,
+    pub revealed_amount: U256,
+    pub revealed_amount_receiver: Address,
+    pub prover_fee: U256,
+    pub prover: Address,
+    pub receiver_post_mint_hook: Bytes,
+    #[serde(default)]
+    pub broadcaster_fee_post_mint_hook: Bytes,
+}
+
+impl MintRequest {
+    pub fn mint_call(&self) -> BETH::mintCoinCall {
+        let proof = &self.proof.proof;
+        BETH::mintCoinCall {
+            _pA: [proof.pi_a[0], proof.pi_a[1]],
+            // pi_b (flipped coordinates)
+            _pB: [
+                [proof.pi_b[0][1], proof.pi_b[0][0]],
+                [proof.pi_b[1][1], proof.pi_b[1][0]],
+            ],
+            _pC: [proof.pi_c[0], proof.pi_c[1]],
+            _blockNumber: U256::from(self.block_number),
+            _nullifier: self.nullifier,
+            _remainingCoin: self.remaining_coin,
+            _broadcasterFee: self.broadcaster_fee,
+            _revealedAmount: self.revealed_amount,
+            _revealedAmountReceiver: self.revealed_amount_receiver,
+            _proverFee: self.prover_fee,
+            _prover: self.prover,
+            _receiverPostMintHook: self.receiver_post_mint_hook.clone(),
+            _broadcasterFeePostMintHook: self.broadcaster_fee_post_mint_hook.clone(),
+        }
+    }
+}
+
+/// Everything a broadcaster needs to submit spendCoin on behalf of a user.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SpendRequest {
+    pub network: String,
+    pub proof: RapidsnarkOutput,
+    pub coin: U256,
+    pub revealed_amount: U256,
+    pub remaining_coin: U256,
+    pub broadcaster_fee: U256,
+    pub receiver: Address,
+}
+
+impl SpendRequest {
+    pub fn spend_call(&self) -> BETH::spendCoinCall {
+        let proof = &self.proof.proof;
+        BETH::spendCoinCall {
+            _pA: [proof.pi_a[0], proof.pi_a[1]],
+            // pi_b (flipped coordinates)
+            _pB: [
+                [proof.pi_b[0][1], proof.pi_b[0][0]],
+                [proof.pi_b[1][1], proof.pi_b[1][0]],
+            ],
+            _pC: [proof.pi_c[0], proof.pi_c[1]],
+            _coin: self.coin,
+            _revealedAmount: self.revealed_amount,
+            _remainingCoin: self.remaining_coin,
+            _broadcasterFee: self.broadcaster_fee,
+            _receiver: self.receiver,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RelayResponse {
+    pub tx_hash: String,
+}
+
+/// Wallet and policy of a server running in relayer mode.
+pub struct Relayer {
+    pub signer: PrivateKeySigner,
+    /// Smallest broadcaster fee worth paying gas for
+    pub min_fee: U256,
+    /// Serializes sends so that concurrent relays don't reuse a nonce
+    pub send_lock: tokio::sync::Mutex<()>,
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct ApiResponse<T> {
     pub status: String,
     pub message: String,
@@ -53,6 +150,7 @@ pub struct AppState {
     pub jobs: Arc<DashMap<Uuid, JobStatus>>,
     pub job_queue: JobQueue,
     pub params_dir: std::path::PathBuf,
+    pub relayer: Option<Arc<Relayer>>,
 }
 
 #[derive(Clone)]
@@ -1,4 +1,4 @@
-use alloy::primitives::{Address, Bytes};
+use alloy::primitives::{Address, B256, Bytes};
 use alloy::providers::Provider;
 
 use anyhow::{Context, Ok, Result as AnyResult, ensure};
@@ -81,6 +81,20 @@ pub async fn verify_proof<P: Provider>(
     provider: &P,
     proof: EIP1186AccountProofResponse,
     block_number: u64,
+) -> AnyResult<()> {
+    let block = provider
+        .get_block_by_number(BlockNumberOrTag::Number(block_number))
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Block not found at height {}", block_number))?;
+
+    verify_proof_against_state_root(&proof, block.header.inner.state_root)
+}
+
+/// Checks an `eth_getProof` response against the state root of the header it
+/// is going to be proven with.
+pub fn verify_proof_against_state_root(
+    proof: &EIP1186AccountProofResponse,
+    state_root: B256,
 ) -> AnyResult<()> {
     let address = proof.address;
 
@@ -103,18 +117,13 @@ pub async fn verify_proof<P: Provider>(
 
     let account_proof: Vec<Vec<u8>> = proof
         .account_proof
-        .into_iter()
-        .map(|b: Bytes| b.to_vec())
+        .iter()
+        .map(|b: &Bytes| b.to_vec())
         .collect();
 
-    let block = provider
-        .get_block_by_number(BlockNumberOrTag::Number(block_number))
-        .await?
-        .ok_or_else(|| anyhow::anyhow!("Block not found at height {}", block_number))?;
-
     let state_root = {
         let mut arr = [0u8; 32];
-        arr.copy_from_slice(block.header.inner.state_root.as_slice());
+        arr.copy_from_slice(state_root.as_slice());
         H256(arr)
     };
 
@@ -14,31 +14,43 @@ use anyhow::anyhow;
 use serde_json::json;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use crate::constants::{poseidon_coin_prefix, poseidon_nullifier_prefix};
+use crate::constants::{
+    poseidon_burn_address_prefix, poseidon_coin_prefix, poseidon_nullifier_prefix,
+};
 use crate::fp::FpRepr;
 use crate::poseidon;
 use crate::poseidon::{poseidon2, poseidon3};
+use crate::server::verify_proof::verify_proof_against_state_root;
+use alloy::consensus::Header;
 use alloy::sol;
 use alloy::{eips::BlockId, rlp::Encodable};
 use alloy_rlp::Decodable;
-use ff::{Field, PrimeField};
+use ff::PrimeField;
 use serde::{Deserialize, Serialize};
 sol!(
     #[allow(missing_docs)]
-    #[sol(rpc)]
+    #[sol(rpc, all_derives)]
     BETH,
     "./src/BETH.abi.json"
 );
 
 sol!(
     #[allow(missing_docs)]
-    #[sol(rpc)]
+    #[sol(rpc, all_derives)]
     WORM,
     "./src/WORM.abi.json"
 );
 
-#[derive(Serialize, Deserialize, Debug)]
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc, all_derives)]
+    Staking,
+    "./src/Staking.abi.json"
+);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RapidsnarkProof {
     pub pi_a: [U256; 3],
     pub pi_b: [[U256; 2]; 3],
@@ -46,28 +58,12 @@ pub struct RapidsnarkProof {
     pub protocol: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RapidsnarkOutput {
     pub proof: RapidsnarkProof,
     pub public: Vec<U256>,
 }
 
-pub fn find_burn_key(pow_min_zero_bytes: usize, burn_extra_commit: U256, reveal: U256) -> Fp {
-    let mut curr: U256 = U256::from_le_bytes(Fp::random(ff::derive::rand_core::OsRng).to_repr().0);
-    loop {
-        let mut inp: [u8; 104] = [0; 104];
-        inp[..32].copy_from_slice(&curr.to_be_bytes::<32>());
-        inp[32..64].copy_from_slice(&reveal.to_be_bytes::<32>());
-        inp[64..96].copy_from_slice(&burn_extra_commit.to_be_bytes::<32>());
-        inp[96..].copy_from_slice(b"EIP-7503");
-        let hash: U256 = keccak256(inp).into();
-        if hash.leading_zeros() >= pow_min_zero_bytes * 8 {
-            return Fp::from_be_bytes(&curr.to_be_bytes::<32>());
-        }
-        curr += U256::ONE;
-    }
-}
-
 pub fn generate_burn_extra_commit(
     receiver: Address,
     prover_fee: U256,
@@ -79,6 +75,11 @@ pub fn generate_burn_extra_commit(
     U256::from_be_slice(keccak256(extra_commit_preimage.as_slice()).as_slice()) >> U256::from(8)
 }
 
+/// Commitment to the prover, the only address allowed to mint a proof.
+pub fn generate_proof_extra_commit(prover: Address) -> U256 {
+    U256::from_be_slice(keccak256(prover.as_slice()).as_slice()) >> U256::from(8)
+}
+
 pub fn generate_burn_address(
     burn_addr_constant: Fp,
     burn_key: Fp,
@@ -100,6 +101,44 @@ pub fn generate_burn_address(
     (Address::from_slice(&hash_be), extra_commitment)
 }
 
+/// What a burn commits to besides its burn-key and amount: the revealed
+/// spend, who receives it and the fees paid out of the mint. The burn address
+/// commits to all of them but the prover, which only the proof commits to and
+/// is therefore unknown until the burn is proved.
+#[derive(Debug, Clone)]
+pub struct BurnCommitment {
+    pub receiver: Address,
+    pub receiver_hook: Bytes,
+    pub broadcaster_fee: U256,
+    pub prover_fee: U256,
+    pub spend: U256,
+    pub prover: Option<Address>,
+}
+
+impl BurnCommitment {
+    pub fn extra_commitment(&self) -> U256 {
+        generate_burn_extra_commit(
+            self.receiver,
+            self.prover_fee,
+            self.broadcaster_fee,
+            self.receiver_hook.clone(),
+        )
+    }
+
+    /// Burn address of `burn_key`, together with its extra commitment.
+    pub fn burn_address(&self, burn_key: Fp) -> (Address, U256) {
+        generate_burn_address(
+            poseidon_burn_address_prefix(),
+            burn_key,
+            self.receiver,
+            self.prover_fee,
+            self.broadcaster_fee,
+            self.spend,
+            self.receiver_hook.clone(),
+        )
+    }
+}
+
 pub fn compute_remaining_coin(
     burn_key: Fp,
     amount: U256,
@@ -139,8 +178,10 @@ struct RlpLeaf {
 pub async fn generate_input_file(
     header_bytes: Vec<u8>,
     burn_key: Fp,
+    intended_balance: U256,
     spend: U256,
     burn_extra_commit: U256,
+    byte_security_relax: u8,
     prover: Address,
     input_path: impl AsRef<Path>,
     proof: EIP1186AccountProofResponse,
@@ -149,8 +190,10 @@ pub async fn generate_input_file(
         proof,
         header_bytes,
         burn_key,
+        intended_balance,
         spend,
         burn_extra_commit,
+        byte_security_relax,
         prover,
     )?
     .to_string();
@@ -184,31 +227,159 @@ pub async fn fetch_block_and_header_bytes<P: Provider>(
 
     Ok((block.header.number, header_bytes))
 }
+/// Fetches the account proof at exactly `block_number`, so that it matches the
+/// header returned by `fetch_block_and_header_bytes` for the same block.
 pub async fn get_account_proof<P: Provider>(
     provider: &P,
     burn_addr: Address,
+    block_number: u64,
 ) -> Result<EIP1186AccountProofResponse> {
-    let proof = provider.get_proof(burn_addr, vec![]).await?;
+    let proof = provider
+        .get_proof(burn_addr, vec![])
+        .number(block_number)
+        .await?;
     Ok(proof)
 }
 
+/// Which block a proof-of-burn is built against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProvingBlockTag {
+    Latest,
+    Safe,
+    Finalized,
+}
+
+impl ProvingBlockTag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProvingBlockTag::Latest => "latest",
+            ProvingBlockTag::Safe => "safe",
+            ProvingBlockTag::Finalized => "finalized",
+        }
+    }
+}
+
+impl FromStr for ProvingBlockTag {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "latest" => Ok(ProvingBlockTag::Latest),
+            "safe" => Ok(ProvingBlockTag::Safe),
+            "finalized" => Ok(ProvingBlockTag::Finalized),
+            _ => Err(anyhow!(
+                "Block tag should be one of: latest, safe, finalized"
+            )),
+        }
+    }
+}
+
+impl From<ProvingBlockTag> for BlockNumberOrTag {
+    fn from(tag: ProvingBlockTag) -> Self {
+        match tag {
+            ProvingBlockTag::Latest => BlockNumberOrTag::Latest,
+            ProvingBlockTag::Safe => BlockNumberOrTag::Safe,
+            ProvingBlockTag::Finalized => BlockNumberOrTag::Finalized,
+        }
+    }
+}
+
+/// Fails if the nullifier of `burn_key` is already spent, i.e. the burn has
+/// been minted and a new proof of it could never land.
+pub async fn check_burn_unminted<P: Provider>(
+    provider: &P,
+    beth: Address,
+    burn_key: Fp,
+) -> Result<()> {
+    let (_, nullifier) = compute_nullifier(burn_key);
+    if BETH::new(beth, provider)
+        .nullifiers(nullifier)
+        .call()
+        .await?
+    {
+        return Err(anyhow!(
+            "The burn is already minted (nullifier {} is spent)",
+            nullifier
+        ));
+    }
+    Ok(())
+}
+
+/// Newest block matching `tag` with at least `confirmations` blocks on top of
+/// it, and whether the burn address holds ETH in it.
+pub async fn proving_block_candidate<P: Provider>(
+    provider: &P,
+    burn_addr: Address,
+    tag: ProvingBlockTag,
+    confirmations: u64,
+) -> Result<(u64, bool)> {
+    let latest = provider.get_block_number().await?;
+    let tagged = provider
+        .get_block_by_number(tag.into())
+        .await?
+        .ok_or(anyhow!("No {} block available!", tag.as_str()))?
+        .header
+        .number;
+    let candidate = std::cmp::min(tagged, latest.saturating_sub(confirmations));
+    let balance = provider.get_balance(burn_addr).number(candidate).await?;
+    Ok((candidate, !balance.is_zero()))
+}
+
+/// Waits until a block matching `tag`, with at least `confirmations` blocks
+/// on top of it, contains the burnt ETH, and returns its number.
+pub async fn wait_for_proving_block<P: Provider>(
+    provider: &P,
+    burn_addr: Address,
+    tag: ProvingBlockTag,
+    confirmations: u64,
+) -> Result<u64> {
+    loop {
+        let (candidate, funded) =
+            proving_block_candidate(provider, burn_addr, tag, confirmations).await?;
+        if funded {
+            return Ok(candidate);
+        }
+        println!(
+            "Waiting for the burn to reach a {} block with {} confirmations (currently at #{})...",
+            tag.as_str(),
+            confirmations,
+            candidate
+        );
+        tokio::time::sleep(std::time::Duration::from_secs(12)).await;
+    }
+}
+
 pub async fn build_and_prove_burn_logic(
     params_dir: &Path,
     header_bytes: Vec<u8>,
     burn_key: Fp,
+    intended_balance: U256,
     spend: U256,
     burn_extra_commit: U256,
+    byte_security_relax: u8,
     prover: Address,
     input_json_path: &str,
     witness_path: &str,
     proof: EIP1186AccountProofResponse,
 ) -> Result<(RapidsnarkOutput, PathBuf)> {
+    // 0) make sure the account proof belongs to the header being proven
+    let header = Header::decode(&mut header_bytes.as_slice())?;
+    verify_proof_against_state_root(&proof, header.state_root).map_err(|e| {
+        anyhow!(
+            "Account proof doesn't match the state root of block #{}: {:?}",
+            header.number,
+            e
+        )
+    })?;
+
     // 1) input.json (delegated)
     generate_input_file(
         header_bytes,
         burn_key,
+        intended_balance,
         spend,
         burn_extra_commit,
+        byte_security_relax,
         prover,
         input_json_path,
         proof,
@@ -278,14 +449,25 @@ pub async fn build_and_prove_burn_logic(
     Ok((json_output, out_path))
 }
 
+/// `intended_balance` is the amount the proof commits to; any ETH the burn
+/// address holds beyond it stays burnt without being minted.
 pub fn input_file(
     proof: EIP1186AccountProofResponse,
     header_bytes: Vec<u8>,
     burn_key: Fp,
+    intended_balance: U256,
     spend: U256,
     burn_extra_commit: U256,
+    byte_security_relax: u8,
     prover: Address,
 ) -> Result<serde_json::Value, anyhow::Error> {
+    if intended_balance > proof.balance {
+        return Err(anyhow!(
+            "The burn address holds {} wei, less than the intended {} wei!",
+            proof.balance,
+            intended_balance
+        ));
+    }
     let max_layers = 16;
     let max_layer_len = 4 * 136;
     let max_header_len = 8 * 136;
@@ -321,12 +503,11 @@ pub fn input_file(
     let mut extended_header = header_bytes.to_vec();
     extended_header.resize(max_header_len, 0);
 
-    let extra_commitment =
-        U256::from_be_slice(keccak256(prover.as_slice()).as_slice()) >> U256::from(8);
+    let extra_commitment = generate_proof_extra_commit(prover);
 
     Ok(json!({
         "actualBalance": proof.balance.to_string(),
-        "intendedBalance": proof.balance.to_string(),
+        "intendedBalance": intended_balance.to_string(),
         "numLayers": proof.account_proof.len(),
         "layerLens": layer_bits_lens,
         "layers": layers,
@@ -336,7 +517,7 @@ pub fn input_file(
         "burnKey": U256::from_le_bytes(burn_key.to_repr().0).to_string(),
         "revealAmount": spend.to_string(),
         "burnExtraCommitment": burn_extra_commit.to_string(),
-        "byteSecurityRelax": 0,
+        "byteSecurityRelax": byte_security_relax,
         "_proofExtraCommitment": extra_commitment.to_string()
     }))
 }